@@ -0,0 +1,244 @@
+use std::collections::VecDeque;
+use std::io::SeekFrom;
+use std::path::PathBuf;
+
+use color_eyre::eyre::bail;
+use tokio::fs::File;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncSeek, AsyncSeekExt, BufReader};
+
+use crate::cbor_decoder::CborDecoder;
+use crate::ftr_parser::{read_chunk_header, read_content_head, Chunk, FtrParser, Payload, RawChunk};
+use crate::types::{FTR, RelationLoading, Transaction};
+
+// largest possible size of a chunk header: tag, array, the fields of a transaction block, the uncompressed size
+// and the head of the byte string, each of them at most 9 bytes
+const MAX_CHUNK_HEADER_SIZE: u64 = 64;
+
+/// Reads the chunks of a FTR file asynchronously. Their headers are decoded with the synchronous decoder once
+/// they are in memory, just like their content, so only the bytes that are needed get read.
+/// The input is read front to back, it is only seeked to skip content and to go to stored positions.
+struct AsyncChunkReader<R> {
+    input_stream: R,
+    // position of the next byte to decode
+    position: u64,
+    // bytes from `position` on that have already been read from the input
+    buffer: Vec<u8>,
+}
+
+impl <R: AsyncRead + AsyncSeek + Unpin> AsyncChunkReader<R> {
+    fn new(input_stream: R) -> Self {
+        Self {input_stream, position: 0, buffer: vec![]}
+    }
+
+    async fn seek(&mut self, position: u64) -> color_eyre::Result<()> {
+        self.buffer.clear();
+        self.position = self.input_stream.seek(SeekFrom::Start(position)).await?;
+        Ok(())
+    }
+
+    // the next bytes of the input, as many as a header can have. They stay buffered until they are consumed.
+    async fn read_head(&mut self) -> color_eyre::Result<&[u8]> {
+        let missing = MAX_CHUNK_HEADER_SIZE.saturating_sub(self.buffer.len() as u64);
+        (&mut self.input_stream).take(missing).read_to_end(&mut self.buffer).await?;
+        Ok(&self.buffer)
+    }
+
+    // drops the buffered bytes before `position`, which a decoder of the head has read
+    fn consume(&mut self, position: u64) {
+        self.buffer.drain(..(position - self.position) as usize);
+        self.position = position;
+    }
+
+    async fn read_bytes(&mut self, len: u64) -> color_eyre::Result<Vec<u8>> {
+        let buffered = self.buffer.len().min(len as usize);
+        let mut buf: Vec<u8> = self.buffer.drain(..buffered).collect();
+        buf.resize(len as usize, 0);
+        self.input_stream.read_exact(&mut buf[buffered..]).await?;
+        self.position += len;
+        Ok(buf)
+    }
+
+    async fn skip_bytes(&mut self, len: u64) -> color_eyre::Result<()> {
+        if len <= self.buffer.len() as u64 {
+            self.consume(self.position + len);
+            Ok(())
+        } else {
+            self.seek(self.position + len).await
+        }
+    }
+
+    async fn read_header(&mut self) -> color_eyre::Result<()> {
+        let position = self.position;
+        let mut cbor_decoder = CborDecoder::new_at(self.read_head().await?, position);
+        FtrParser::parse_header(&mut cbor_decoder)?;
+        let position = cbor_decoder.position();
+        self.consume(position);
+        Ok(())
+    }
+
+    // same as 'ftr_parser::read_chunk()', but the content of transaction blocks and relationship chunks is always skipped
+    async fn read_chunk(&mut self) -> color_eyre::Result<RawChunk> {
+        let position = self.position;
+        let mut cbor_decoder = CborDecoder::new_at(self.read_head().await?, position);
+        let header = match read_chunk_header(&mut cbor_decoder)? {
+            Some(header) => header,
+            None => return Ok(RawChunk::End),
+        };
+        let position = cbor_decoder.position();
+        self.consume(position);
+        let bytes = if header.content_needed(false, false) {
+            Some(self.read_bytes(header.len).await?)
+        } else {
+            self.skip_bytes(header.len).await?;
+            None
+        };
+        Ok(header.into_raw_chunk(bytes))
+    }
+
+    // reads the content of a transaction block or relationship chunk from the position that was stored for it
+    async fn read_stored_payload(&mut self, (position, compressed): (u64, bool)) -> color_eyre::Result<Payload> {
+        self.seek(position).await?;
+        let mut cbor_decoder = CborDecoder::new_at(self.read_head().await?, position);
+        let (uncomp_size, len) = read_content_head(&mut cbor_decoder, compressed)?;
+        let position = cbor_decoder.position();
+        self.consume(position);
+        Ok(Payload{bytes: self.read_bytes(len).await?, uncomp_size})
+    }
+}
+
+/// Async version of `parse_ftr()` for readers implementing tokio's `AsyncRead` and `AsyncSeek`.
+///
+/// Creating the reader parses streams and generators, while the transactions are loaded lazily,
+/// either into memory with `load_stream_into_memory()` or one by one with `transactions()`.
+/// Relations are loaded together with the transactions they come from or point to, or all at once with `load_relations()`.
+pub struct AsyncFtrReader<R> {
+    ftr: FTR,
+    chunk_reader: AsyncChunkReader<R>,
+}
+
+impl AsyncFtrReader<BufReader<File>> {
+    /// Opens the FTR file with the given name.
+    /// As the FTR keeps the file name, its synchronous `load_stream_into_memory()` can be used as well.
+    pub async fn open(file_name: PathBuf) -> color_eyre::Result<Self> {
+        let file = File::open(&file_name).await?;
+        let mut ftr_reader = Self::new(BufReader::new(file)).await?;
+        ftr_reader.ftr.path = Some(file_name);
+        Ok(ftr_reader)
+    }
+}
+
+impl <R: AsyncRead + AsyncSeek + Unpin> AsyncFtrReader<R> {
+    /// Parses everything but the transactions from the reader, which has to be at the start of the FTR data.
+    ///
+    /// Unlike with `open()`, the FTR doesn't know where its data comes from, so the calls of the FTR that load what
+    /// is missing, like `get_transaction()`, `follow_outgoing()` or `load_relations()`, return an error if it is not
+    /// in memory yet. Load the streams and relations with this reader first.
+    pub async fn new(reader: R) -> color_eyre::Result<Self> {
+        let mut chunk_reader = AsyncChunkReader::new(reader);
+        chunk_reader.read_header().await?;
+
+        let mut ftr = FTR::new(None);
+        // relationship chunks are skipped like the transaction blocks, the reader can read them later on
+        ftr.relation_loading = RelationLoading::LoadedStreams;
+        loop {
+            let raw_chunk = chunk_reader.read_chunk().await?;
+            if let Chunk::End = FtrParser::new(&mut ftr).apply_chunk(raw_chunk)? {
+                break;
+            }
+        }
+
+        Ok(Self {ftr, chunk_reader})
+    }
+
+    pub fn ftr(&self) -> &FTR {
+        &self.ftr
+    }
+
+    pub fn ftr_mut(&mut self) -> &mut FTR {
+        &mut self.ftr
+    }
+
+    pub fn into_ftr(self) -> FTR {
+        self.ftr
+    }
+
+    /// Takes a stream id and loads all associated transactions into memory
+    pub async fn load_stream_into_memory(&mut self, stream_id: usize) -> color_eyre::Result<()> {
+        let tx_block_ids = self.tx_block_ids(stream_id)?;
+
+        self.ftr.drop_stream_from_memory(stream_id);
+        let mut ftr_parser = FtrParser::new(&mut self.ftr);
+        for (block_idx, tx_block_id) in tx_block_ids.into_iter().enumerate() {
+            let payload = self.chunk_reader.read_stored_payload(tx_block_id).await?;
+            ftr_parser.add_transactions(stream_id, block_idx, payload)?;
+        }
+        for (chunk_idx, chunk_id) in ftr_parser.relation_chunks_to_read() {
+            let payload = self.chunk_reader.read_stored_payload(chunk_id).await?;
+            ftr_parser.add_relations(chunk_idx, payload, false)?;
+        }
+        self.ftr.tx_streams.get_mut(&stream_id).unwrap().transactions_loaded = true;
+        self.ftr.index_stream(stream_id);
+        Ok(())
+    }
+
+    /// Loads all relations into `FTR::relations()`, otherwise only the relations of loaded transactions are there.
+    pub async fn load_relations(&mut self) -> color_eyre::Result<()> {
+        if self.ftr.relations_loaded() {
+            return Ok(());
+        }
+        let mut ftr_parser = FtrParser::new(&mut self.ftr);
+        for (chunk_idx, chunk_id) in ftr_parser.all_relation_chunks() {
+            let payload = self.chunk_reader.read_stored_payload(chunk_id).await?;
+            ftr_parser.add_relations(chunk_idx, payload, true)?;
+        }
+        ftr_parser.set_relations_loaded();
+        Ok(())
+    }
+
+    /// Reads the transactions of a stream one transaction block at a time, without keeping them in memory.
+    pub fn transactions(&mut self, stream_id: usize) -> color_eyre::Result<AsyncTransactions<'_, R>> {
+        let tx_block_ids = self.tx_block_ids(stream_id)?;
+        Ok(AsyncTransactions {
+            ftr_reader: self,
+            tx_block_ids: tx_block_ids.into(),
+            pending: VecDeque::new(),
+        })
+    }
+
+    // positions of the transaction blocks of the stream, and whether they are compressed
+    fn tx_block_ids(&self, stream_id: usize) -> color_eyre::Result<Vec<(u64, bool)>> {
+        match self.ftr.tx_streams.get(&stream_id) {
+            Some(stream) => Ok(stream.tx_blocks.iter().map(|b| (b.position, b.compressed)).collect()),
+            None => bail!("There is no stream with id {stream_id}"),
+        }
+    }
+
+}
+
+/// The transactions of a single stream, see `AsyncFtrReader::transactions()`.
+pub struct AsyncTransactions<'a, R> {
+    ftr_reader: &'a mut AsyncFtrReader<R>,
+    tx_block_ids: VecDeque<(u64, bool)>,
+    pending: VecDeque<Transaction>,
+}
+
+impl <R: AsyncRead + AsyncSeek + Unpin> AsyncTransactions<'_, R> {
+    /// Returns the next transaction of the stream, or `None` after the last one.
+    pub async fn next(&mut self) -> Option<color_eyre::Result<Transaction>> {
+        while self.pending.is_empty() {
+            let tx_block_id = self.tx_block_ids.pop_front()?;
+            let transactions = match self.ftr_reader.chunk_reader.read_stored_payload(tx_block_id).await {
+                Ok(payload) => payload.decoder().and_then(|mut cbd| FtrParser::new(&mut self.ftr_reader.ftr).parse_tx_block(&mut cbd)),
+                Err(e) => Err(e),
+            };
+            match transactions {
+                Ok(transactions) => self.pending.extend(transactions),
+                Err(e) => {
+                    self.tx_block_ids.clear();
+                    return Some(Err(e));
+                }
+            }
+        }
+        self.pending.pop_front().map(Ok)
+    }
+}
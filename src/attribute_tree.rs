@@ -0,0 +1,162 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::types::Attribute;
+
+/// Attributes of a transaction arranged by their names, so that flattened payloads like `trans.address`,
+/// `trans.data[0]` and `trans.data[1]` become the record `trans` with the field `address` and the array `data`.
+///
+/// If several attributes end up at the same place, e.g. because a BEGIN and an END attribute have the same name,
+/// the first one is kept. Every part in brackets that is an `usize` is an index, arrays only hold the elements
+/// that have an attribute, so `data[4000000000]` doesn't take more memory than `data[0]`.
+#[derive(Debug, Clone)]
+pub enum AttributeNode<'a> {
+    Value(&'a Attribute),
+    /// Fields in the order of their first attribute
+    Record(Vec<(&'a str, AttributeNode<'a>)>),
+    /// Elements by their index, only those that have an attribute
+    Array(BTreeMap<usize, AttributeNode<'a>>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Segment<'a> {
+    Field(&'a str),
+    Index(usize),
+}
+
+impl<'a> AttributeNode<'a> {
+    /// Builds the tree of the given attributes, with the root being a record.
+    pub fn new(attributes: impl IntoIterator<Item = &'a Attribute>) -> Self {
+        let mut root = AttributeNode::Record(vec![]);
+        for attr in attributes {
+            root.insert(&split_name(&attr.name), attr);
+        }
+        root
+    }
+
+    // a record with the attributes under their full names
+    pub(crate) fn flat(attributes: impl IntoIterator<Item = &'a Attribute>) -> Self {
+        let mut root = AttributeNode::Record(vec![]);
+        for attr in attributes {
+            root.insert(&[Segment::Field(&attr.name)], attr);
+        }
+        root
+    }
+
+    // adds an attribute below this node, unless its place is already taken
+    fn insert(&mut self, path: &[Segment<'a>], attr: &'a Attribute) {
+        let Some((segment, rest)) = path.split_first() else { return };
+        let new_node = || match rest.first() {
+            None => AttributeNode::Value(attr),
+            Some(Segment::Field(_)) => AttributeNode::Record(vec![]),
+            Some(Segment::Index(_)) => AttributeNode::Array(BTreeMap::new()),
+        };
+        let child = match (self, segment) {
+            (AttributeNode::Record(fields), Segment::Field(name)) => {
+                let position = match fields.iter().position(|(field, _)| field == name) {
+                    Some(position) => position,
+                    None => {
+                        fields.push((name, new_node()));
+                        fields.len() - 1
+                    }
+                };
+                &mut fields[position].1
+            }
+            (AttributeNode::Array(elements), Segment::Index(index)) => elements.entry(*index).or_insert_with(new_node),
+            _ => return,
+        };
+        child.insert(rest, attr);
+    }
+
+    /// Returns the node at the given path, e.g. `trans.data[1]`.
+    pub fn get(&self, path: &str) -> Option<&AttributeNode<'a>> {
+        path.split('.').try_fold(self, |node, part| {
+            match split_indices(part) {
+                Some((field, indices)) => indices.iter().try_fold(node.field(field)?, |node, index| node.element(*index)),
+                None => node.field(part),
+            }
+        })
+    }
+
+    /// Returns the field of a record with the given name.
+    pub fn field(&self, name: &str) -> Option<&AttributeNode<'a>> {
+        match self {
+            AttributeNode::Record(fields) => fields.iter().find(|(field, _)| *field == name).map(|(_, node)| node),
+            _ => None,
+        }
+    }
+
+    /// Returns the element of an array with the given index.
+    pub fn element(&self, index: usize) -> Option<&AttributeNode<'a>> {
+        match self {
+            AttributeNode::Array(elements) => elements.get(&index),
+            _ => None,
+        }
+    }
+
+    /// Returns the attribute of a value node.
+    pub fn attribute(&self) -> Option<&'a Attribute> {
+        match self {
+            AttributeNode::Value(attr) => Some(attr),
+            _ => None,
+        }
+    }
+
+    fn fmt_children(&self, f: &mut fmt::Formatter, depth: usize) -> fmt::Result {
+        let indent = "  ".repeat(depth);
+        let children: Vec<(String, &AttributeNode)> = match self {
+            AttributeNode::Value(_) => vec![],
+            AttributeNode::Record(fields) => fields.iter().map(|(name, node)| (name.to_string(), node)).collect(),
+            AttributeNode::Array(elements) => elements.iter().map(|(index, node)| (format!("[{}]", index), node)).collect(),
+        };
+        for (name, node) in children {
+            match node {
+                AttributeNode::Value(attr) => writeln!(f, "{}{} = {}", indent, name, attr.value())?,
+                _ => {
+                    writeln!(f, "{}{}", indent, name)?;
+                    node.fmt_children(f, depth + 1)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Shows one node per line, with the children of records and arrays indented below them.
+impl fmt::Display for AttributeNode<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AttributeNode::Value(attr) => writeln!(f, "{}", attr.value()),
+            _ => self.fmt_children(f, 0),
+        }
+    }
+}
+
+// splits a name like `trans.data[0]` into `trans`, `data` and `0`.
+// Parts with brackets that don't contain an index are taken as they are.
+fn split_name(name: &str) -> Vec<Segment<'_>> {
+    let mut segments = vec![];
+    for part in name.split('.') {
+        match split_indices(part) {
+            Some((field, indices)) => {
+                segments.push(Segment::Field(field));
+                segments.extend(indices.into_iter().map(Segment::Index));
+            }
+            None => segments.push(Segment::Field(part)),
+        }
+    }
+    segments
+}
+
+fn split_indices(part: &str) -> Option<(&str, Vec<usize>)> {
+    let (field, mut rest) = part.split_once('[')?;
+    let mut indices = vec![];
+    loop {
+        let (index, after) = rest.split_once(']')?;
+        indices.push(index.parse().ok()?);
+        if after.is_empty() {
+            return Some((field, indices));
+        }
+        rest = after.strip_prefix('[')?;
+    }
+}
@@ -1,4 +1,5 @@
-use std::io::{Read, Seek, SeekFrom};
+use std::fs::File;
+use std::io::{Cursor, Read, Seek, SeekFrom};
 use color_eyre::eyre::bail;
 use color_eyre::Report;
 use half::f16;
@@ -25,16 +26,98 @@ const HALF_PRECISION_FLOAT: u8 = 0x19;
 const SINGLE_PRECISION_FLOAT: u8 = 0x1a;
 const DOUBLE_PRECISION_FLOAT: u8 = 0x1b;
 
+/// An input the decoder can jump over data in, without having to decode it.
+/// Seekable inputs simply seek, everything else reads and discards the bytes.
+pub trait SkipInput: Read {
+    fn skip(&mut self, len: u64) -> std::io::Result<()>;
+}
+
+impl SkipInput for File {
+    fn skip(&mut self, len: u64) -> std::io::Result<()> {
+        skip_in_file(self, len)
+    }
+}
+
+impl SkipInput for &File {
+    fn skip(&mut self, len: u64) -> std::io::Result<()> {
+        skip_in_file(self, len)
+    }
+}
+
+// seeking is allowed past the end of a file, so this has to be checked, e.g. for files that are still being written
+fn skip_in_file(mut file: &File, len: u64) -> std::io::Result<()> {
+    let position = file.stream_position()?;
+    if position + len > file.metadata()?.len() {
+        return Err(std::io::ErrorKind::UnexpectedEof.into());
+    }
+    file.seek(SeekFrom::Current(len as i64)).map(|_| ())
+}
+
+impl <T: AsRef<[u8]>> SkipInput for Cursor<T> {
+    fn skip(&mut self, len: u64) -> std::io::Result<()> {
+        self.seek(SeekFrom::Current(len as i64)).map(|_| ())
+    }
+}
+
+/// Wrapper for readers that cannot seek (pipes, stdin, sockets, decompressors).
+pub struct SequentialReader<R>(pub R);
+
+impl <R: Read> Read for SequentialReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.0.read(buf)
+    }
+}
+
+impl <R: Read> SkipInput for SequentialReader<R> {
+    fn skip(&mut self, len: u64) -> std::io::Result<()> {
+        let skipped = std::io::copy(&mut (&mut self.0).take(len), &mut std::io::sink())?;
+        if skipped != len {
+            return Err(std::io::ErrorKind::UnexpectedEof.into());
+        }
+        Ok(())
+    }
+}
+
 pub struct CborDecoder<R>{
     pub(crate) input_stream: R,
     peek_buf: Vec<u8>,
+    position: u64,
 }
 
-impl <R: Read + Seek>CborDecoder<R>{
+impl <R: Read>CborDecoder<R>{
     pub fn new(input_stream: R) -> Self {
         let mut peek_buf = vec![0u8; 1];
         peek_buf.clear();
-        Self {input_stream, peek_buf}
+        Self {input_stream, peek_buf, position: 0}
+    }
+
+    /// Creates a decoder for an input that is already at `position`, e.g. after seeking into a file.
+    pub fn new_at(input_stream: R, position: u64) -> Self {
+        let mut cbor_decoder = Self::new(input_stream);
+        cbor_decoder.position = position;
+        cbor_decoder
+    }
+
+    /// Position in the input, i.e. the number of bytes consumed since this decoder was created,
+    /// plus the start position given to `new_at()`. A peeked byte does not count as consumed.
+    pub fn position(&self) -> u64 {
+        self.position - self.peek_buf.len() as u64
+    }
+
+    fn read_exact(&mut self, buf: &mut [u8]) -> std::io::Result<()> {
+        self.input_stream.read_exact(buf)?;
+        self.position += buf.len() as u64;
+        Ok(())
+    }
+
+    // returns the peeked byte if there is one, otherwise reads the next byte from the input
+    fn read_byte(&mut self) -> std::io::Result<u8> {
+        if let Some(b) = self.peek_buf.pop() {
+            return Ok(b);
+        }
+        let mut buf = [0u8; 1];
+        self.read_exact(&mut buf)?;
+        Ok(buf[0])
     }
 
     pub fn read_tag(&mut self) -> color_eyre::Result<i64> {
@@ -44,13 +127,7 @@ impl <R: Read + Seek>CborDecoder<R>{
 
 
     pub fn read_major_type(&mut self, major_type: u8) -> color_eyre::Result<u8> {
-        let mut buf = vec![0u8; 1];
-        if !self.peek_buf.is_empty() {
-            buf[0] = self.peek_buf[0];
-            self.peek_buf.clear();
-        } else {
-            self.input_stream.read_exact(&mut buf)?
-        };
+        let buf = [self.read_byte()?];
 
         if major_type != ((buf[0] >> 5) & 0x07) {
             bail!("Incorrect major type!")
@@ -95,25 +172,25 @@ impl <R: Read + Seek>CborDecoder<R>{
 
     fn read_unsigned_int_8(&mut self) -> color_eyre::Result<i64> {
         let mut buf = vec![0u8; 1];
-        self.input_stream.read_exact(&mut buf)?;
+        self.read_exact(&mut buf)?;
         Ok(buf[0] as i64)
     }
 
     fn read_unsigned_int_16(&mut self) -> color_eyre::Result<i64> {
         let mut buf = vec![0u8; 2];
-        self.input_stream.read_exact(&mut buf)?;
+        self.read_exact(&mut buf)?;
         Ok((buf[0] as i64) << 8 | (buf[1] as i64))
     }
 
     fn read_unsigned_int_32(&mut self) -> color_eyre::Result<i64> {
         let mut buf = vec![0u8; 4];
-        self.input_stream.read_exact(&mut buf)?;
+        self.read_exact(&mut buf)?;
         Ok((buf[0] as i64) << 24 | (buf[1] as i64) << 16 | (buf[2] as i64) << 8 | (buf[3] as i64))
     }
 
     fn read_unsigned_int_64(&mut self) -> color_eyre::Result<i64> {
         let mut buf = vec![0u8; 8];
-        self.input_stream.read_exact(&mut buf)?;
+        self.read_exact(&mut buf)?;
         Ok((buf[0] as i64) << 56 | (buf[1] as i64) << 48 | (buf[2] as i64) << 40 | (buf[3] as i64) << 32 | //
             (buf[4] as i64) << 24 | (buf[5] as i64) << 16 | (buf[6] as i64) << 8 | (buf[7] as i64))
     }
@@ -144,24 +221,27 @@ impl <R: Read + Seek>CborDecoder<R>{
     }
 
 
-    pub fn read_byte_string(&mut self) -> color_eyre::Result<Vec<u8>> {
+    /// Reads the head of a byte string and returns its length, the bytes themselves are left in the input.
+    pub fn read_byte_string_length(&mut self) -> color_eyre::Result<u64> {
         let len = Self::read_major_type_with_size(self, TYPE_BYTE_STRING)?;
-        let mut buf = vec![0u8; len as usize];
-        self.input_stream.read_exact(&mut buf)?;
-        Ok(buf)
+        if len < 0 {
+            bail!("Infinite length byte strings are not supported!")
+        }
+        Ok(len as u64)
     }
 
-    pub fn skip_byte_string(&mut self) -> color_eyre::Result<()> {
-        let len = Self::read_major_type_with_size(self, TYPE_BYTE_STRING)?;
-        match self.input_stream.seek(SeekFrom::Current(len)) {
-            Ok(_) => Ok(()),
-            Err(e) => Err(Report::new(e)),
+    /// Reads the given number of bytes, e.g. the content of a byte string after `read_byte_string_length()`.
+    pub fn read_bytes(&mut self, len: u64) -> color_eyre::Result<Vec<u8>> {
+        if len > i64::MAX as u64 {
+            bail!("Byte string length too long!")
         }
+        let mut buf = vec![0u8; len as usize];
+        self.read_exact(&mut buf)?;
+        Ok(buf)
     }
 
     pub fn read_int(&mut self) -> color_eyre::Result<i64> {
-        let mut buf = vec![0u8; 1];
-        self.input_stream.read_exact(&mut buf)?;
+        let buf = [self.read_byte()?];
 
         let ui = Self::expect_integer_type(self, buf[0])?;
 
@@ -169,6 +249,7 @@ impl <R: Read + Seek>CborDecoder<R>{
     }
 
     pub fn expect_integer_type(&mut self, ib: u8) -> color_eyre::Result<i64> {
+        #[allow(clippy::identity_op)]
         let major_type = (ib & 0xff) >> 5;
         if (major_type != TYPE_UNSIGNED_INT) && (major_type != TYPE_NEGATIVE_INT) {
             Err(color_eyre::Report::msg("Expected Integer type!"))
@@ -186,11 +267,7 @@ impl <R: Read + Seek>CborDecoder<R>{
         if len < 0 {
             bail!("Infinite length text are not supported!")
         }
-        if len > i64::MAX {
-            bail!("String length too long!")
-        }
-        let mut buf = vec![0u8; len as usize];
-        self.input_stream.read_exact(&mut buf)?;
+        let buf = self.read_bytes(len as u64)?;
         match String::from_utf8(buf) {
             Ok(string) => Ok(string),
             Err(e) => Err(Report::new(e))
@@ -198,9 +275,29 @@ impl <R: Read + Seek>CborDecoder<R>{
     }
 
     pub fn peek(&mut self)  -> color_eyre::Result<i64> {
-        self.peek_buf = vec![0u8; 1];
-        self.input_stream.read_exact(&mut self.peek_buf)?;
+        if let Some(b) = self.peek_buf.first() {
+            return Ok(*b as i64);
+        }
+        let mut buf = vec![0u8; 1];
+        self.read_exact(&mut buf)?;
+        self.peek_buf = buf;
         Ok(self.peek_buf[0] as i64)
     }
 }
 
+impl <R: SkipInput>CborDecoder<R>{
+    /// Skips the given number of bytes, e.g. the content of a byte string after `read_byte_string_length()`.
+    pub fn skip_bytes(&mut self, len: u64) -> color_eyre::Result<()> {
+        if len > i64::MAX as u64 {
+            bail!("Byte string length too long!")
+        }
+        match self.input_stream.skip(len) {
+            Ok(_) => {
+                self.position += len;
+                Ok(())
+            },
+            Err(e) => Err(Report::new(e)),
+        }
+    }
+}
+
@@ -0,0 +1,91 @@
+use std::collections::BTreeMap;
+
+use num_bigint::BigUint;
+use serde::Serialize;
+
+use crate::types::Transaction;
+
+/// The number of transactions in flight over time, as a step function built from their start and end times,
+/// see `FTR::concurrency()`. A transaction is in flight from its start time up to, but not including, its end time.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ConcurrencyProfile {
+    steps: Vec<ConcurrencyStep>,
+}
+
+/// The number of transactions in flight from `time` up to the time of the next step.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ConcurrencyStep {
+    pub time: BigUint,
+    pub count: usize,
+}
+
+impl ConcurrencyProfile {
+    pub fn new<'a>(transactions: impl IntoIterator<Item = &'a Transaction>) -> Self {
+        // change of the count at each time
+        let mut changes: BTreeMap<&BigUint, i64> = BTreeMap::new();
+        for tx in transactions {
+            if tx.event.end_time > tx.event.start_time {
+                *changes.entry(&tx.event.start_time).or_default() += 1;
+                *changes.entry(&tx.event.end_time).or_default() -= 1;
+            }
+        }
+
+        let mut steps = vec![];
+        let mut count = 0i64;
+        for (time, change) in changes {
+            if change != 0 {
+                count += change;
+                steps.push(ConcurrencyStep { time: time.clone(), count: count as usize });
+            }
+        }
+        Self { steps }
+    }
+
+    /// Returns the steps ordered by time. The last step is the end of the last transaction, where the count drops to 0.
+    pub fn steps(&self) -> &[ConcurrencyStep] {
+        &self.steps
+    }
+
+    /// Returns the number of transactions in flight at `time`.
+    pub fn at(&self, time: &BigUint) -> usize {
+        let position = self.steps.partition_point(|step| step.time <= *time);
+        if position == 0 { 0 } else { self.steps[position - 1].count }
+    }
+
+    /// Returns the largest number of transactions in flight at the same time.
+    pub fn max(&self) -> usize {
+        self.steps.iter().map(|step| step.count).max().unwrap_or(0)
+    }
+
+    /// Returns the average number of transactions in flight, weighted by time, from the start of the first
+    /// transaction to the end of the last one.
+    pub fn average(&self) -> f64 {
+        let (Some(first), Some(last)) = (self.steps.first(), self.steps.last()) else { return 0.0 };
+        if last.time == first.time {
+            return 0.0;
+        }
+        let busy: BigUint = self.steps.windows(2).map(|pair| (&pair[1].time - &pair[0].time) * pair[0].count).sum();
+        to_f64(&busy) / to_f64(&(&last.time - &first.time))
+    }
+
+    /// Returns the intervals from start to end in which more than `threshold` transactions are in flight.
+    pub fn intervals_above(&self, threshold: usize) -> Vec<(BigUint, BigUint)> {
+        let mut intervals = vec![];
+        let mut start: Option<&BigUint> = None;
+        for step in &self.steps {
+            match (start, step.count > threshold) {
+                (None, true) => start = Some(&step.time),
+                (Some(from), false) => {
+                    intervals.push((from.clone(), step.time.clone()));
+                    start = None;
+                }
+                _ => {}
+            }
+        }
+        intervals
+    }
+}
+
+pub(crate) fn to_f64(value: &BigUint) -> f64 {
+    value.to_u64_digits().iter().rev().fold(0.0, |acc, digit| acc * 2f64.powi(64) + *digit as f64)
+}
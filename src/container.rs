@@ -0,0 +1,78 @@
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+#[cfg(any(feature = "gzip", feature = "zstd", feature = "xz"))]
+use std::io::BufReader;
+
+use color_eyre::eyre::eyre;
+use color_eyre::Report;
+
+const GZIP_MAGIC: &[u8] = &[0x1f, 0x8b];
+const ZSTD_MAGIC: &[u8] = &[0x28, 0xb5, 0x2f, 0xfd];
+const XZ_MAGIC: &[u8] = &[0xfd, 0x37, 0x7a, 0x58, 0x5a, 0x00];
+
+/// The container format a FTR file is wrapped in.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Container {
+    Plain,
+    Gzip,
+    Zstd,
+    Xz,
+}
+
+impl Container {
+    /// Detects the container by its magic bytes. The file is rewound to the start afterward.
+    pub(crate) fn detect(file: &mut File) -> color_eyre::Result<Container> {
+        let mut magic = Vec::with_capacity(XZ_MAGIC.len());
+        file.take(XZ_MAGIC.len() as u64).read_to_end(&mut magic)?;
+        file.seek(SeekFrom::Start(0))?;
+
+        let container = if magic.starts_with(GZIP_MAGIC) {
+            Container::Gzip
+        } else if magic.starts_with(ZSTD_MAGIC) {
+            Container::Zstd
+        } else if magic.starts_with(XZ_MAGIC) {
+            Container::Xz
+        } else {
+            Container::Plain
+        };
+        Ok(container)
+    }
+
+    /// Error for containers whose cargo feature is not enabled.
+    pub(crate) fn missing_feature(&self) -> Report {
+        let feature = match self {
+            Container::Plain => "",
+            Container::Gzip => "gzip",
+            Container::Zstd => "zstd",
+            Container::Xz => "xz",
+        };
+        eyre!("The file is {:?} compressed. Enable the `{}` feature of ftr_parser to read it.", self, feature)
+    }
+
+    /// Wraps the file into a decoder for this container.
+    #[cfg(any(feature = "gzip", feature = "zstd", feature = "xz"))]
+    pub(crate) fn decoder(&self, file: File) -> color_eyre::Result<Box<dyn Read>> {
+        let decoder: Box<dyn Read> = match self {
+            Container::Plain => Box::new(file),
+            #[cfg(feature = "gzip")]
+            Container::Gzip => Box::new(flate2::read::MultiGzDecoder::new(BufReader::new(file))),
+            #[cfg(feature = "zstd")]
+            Container::Zstd => Box::new(zstd::Decoder::new(file)?),
+            #[cfg(feature = "xz")]
+            Container::Xz => Box::new(xz2::read::XzDecoder::new_multi_decoder(BufReader::new(file))),
+            #[allow(unreachable_patterns)]
+            _ => return Err(self.missing_feature()),
+        };
+        Ok(decoder)
+    }
+}
+
+/// Decompresses the whole file into a temporary file, which is deleted again once the returned path is dropped.
+/// Transactions can then be loaded lazily from the temporary file, just like from an uncompressed FTR file.
+#[cfg(any(feature = "gzip", feature = "zstd", feature = "xz"))]
+pub(crate) fn decompress_to_temp(container: Container, file: File) -> color_eyre::Result<tempfile::TempPath> {
+    let mut decoder = container.decoder(file)?;
+    let mut temp = tempfile::Builder::new().prefix("ftr_parser").suffix(".ftr").tempfile()?;
+    std::io::copy(&mut decoder, temp.as_file_mut())?;
+    Ok(temp.into_temp_path())
+}
@@ -0,0 +1,223 @@
+use std::collections::btree_map;
+use std::iter::Peekable;
+
+use serde::de::value::{Error, StrDeserializer, UnitDeserializer};
+use serde::de::{self, DeserializeSeed, IntoDeserializer, MapAccess, SeqAccess, Visitor};
+use serde::forward_to_deserialize_any;
+
+use crate::attribute_tree::AttributeNode;
+use crate::types::{AttributeType, DataType, Transaction};
+
+/// Selects which attributes of a transaction are deserialized and how their names are mapped to fields.
+#[derive(Debug, Clone, Default)]
+pub struct AttributeOptions {
+    kinds: Vec<AttributeType>,
+    nested: bool,
+}
+
+impl AttributeOptions {
+    /// All attributes, with their full names as field names.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only attributes of the given kind are deserialized. Can be called several times to select several kinds.
+    pub fn kind(mut self, kind: AttributeType) -> Self {
+        self.kinds.push(kind);
+        self
+    }
+
+    /// Splits attribute names at dots, so that e.g. `wr.addr` becomes the field `addr` of the nested struct `wr`,
+    /// and reads names like `data[0]` as elements of sequences, see `AttributeNode`.
+    pub fn nested(mut self, nested: bool) -> Self {
+        self.nested = nested;
+        self
+    }
+}
+
+/// A serde `Deserializer` that reads the attributes of a transaction as a map from attribute names to values,
+/// so that they can be deserialized into structs, see `Transaction::deserialize_attributes()`.
+///
+/// Integer, floating point, boolean and string-like attributes are mapped to the corresponding serde types,
+/// bit vectors without x or z bits can also be read as integers. If several attributes have the same name,
+/// the first one is used.
+///
+/// Arrays whose elements all have an index below `MAX_SEQUENCE_LEN` are read as sequences, with `None` where no
+/// attribute has the index. Arrays with larger indices are read as maps from the index to the element, so that
+/// e.g. `data[4000000000]` can be read into a `BTreeMap<usize, T>` instead of a sequence of four billion elements.
+pub struct AttributeDeserializer<'a> {
+    node: AttributeNode<'a>,
+}
+
+impl<'a> AttributeDeserializer<'a> {
+    pub fn new(tx: &'a Transaction, options: &AttributeOptions) -> Self {
+        let attributes = tx.attributes
+            .iter()
+            .filter(|attr| options.kinds.is_empty() || options.kinds.contains(&attr.kind));
+        let node = if options.nested {
+            AttributeNode::new(attributes)
+        } else {
+            AttributeNode::flat(attributes)
+        };
+        Self { node }
+    }
+}
+
+impl<'de> de::Deserializer<'de> for AttributeDeserializer<'de> {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        NodeDeserializer(self.node).deserialize_any(visitor)
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option unit unit_struct newtype_struct seq tuple
+        tuple_struct map struct enum identifier ignored_any
+    }
+}
+
+/// How long the sequences that arrays are read as can get, see `AttributeDeserializer`.
+pub const MAX_SEQUENCE_LEN: usize = 1 << 16;
+
+// records are deserialized as maps, arrays as sequences
+struct NodeDeserializer<'de>(AttributeNode<'de>);
+
+impl NodeDeserializer<'_> {
+    // vectors that only consist of 0 and 1 can be read as integers
+    fn bits(&self) -> Option<u128> {
+        match self.0.attribute().map(|attr| &attr.data_type) {
+            Some(DataType::BitVector(s) | DataType::LogicVector(s)) => u128::from_str_radix(s, 2).ok(),
+            _ => None,
+        }
+    }
+}
+
+macro_rules! deserialize_integer {
+    ($($method:ident)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+                match self.bits() {
+                    Some(bits) => match u64::try_from(bits) {
+                        Ok(bits) => visitor.visit_u64(bits),
+                        Err(_) => visitor.visit_u128(bits),
+                    },
+                    None => self.deserialize_any(visitor),
+                }
+            }
+        )*
+    };
+}
+
+impl<'de> de::Deserializer<'de> for NodeDeserializer<'de> {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self.0 {
+            AttributeNode::Record(fields) => visitor.visit_map(RecordAccess { fields: fields.into_iter(), value: None }),
+            AttributeNode::Array(elements) => match elements.keys().next_back().map_or(Some(0), |index| index.checked_add(1)) {
+                Some(len) if len <= MAX_SEQUENCE_LEN => visitor.visit_seq(ArrayAccess { elements: elements.into_iter().peekable(), index: 0, len }),
+                _ => visitor.visit_map(RecordAccess { fields: elements.into_iter(), value: None }),
+            },
+            AttributeNode::Value(attr) => match &attr.data_type {
+                DataType::Boolean(b) => visitor.visit_bool(*b),
+                DataType::Integer(i) => visitor.visit_i64(*i),
+                DataType::Unsigned(u) | DataType::Pointer(u) | DataType::Time(u) => visitor.visit_u64(*u),
+                DataType::FloatingPointNumber(f) | DataType::FixedPointInteger(f) | DataType::UnsignedFixedPointInteger(f) => visitor.visit_f32(*f),
+                DataType::Enumeration(s) | DataType::String(s) | DataType::BitVector(s) | DataType::LogicVector(s) => visitor.visit_borrowed_str(s),
+                DataType::Error => visitor.visit_unit(),
+            },
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self.0.attribute().map(|attr| &attr.data_type) {
+            Some(DataType::Error) => visitor.visit_none(),
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(self, _name: &'static str, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(self, name: &'static str, variants: &'static [&'static str], visitor: V) -> Result<V::Value, Error> {
+        match self.0.attribute().map(|attr| &attr.data_type) {
+            // enumeration attributes select a unit variant by its name
+            Some(DataType::Enumeration(s) | DataType::String(s)) => {
+                let deserializer: StrDeserializer<Error> = s.as_str().into_deserializer();
+                deserializer.deserialize_enum(name, variants, visitor)
+            }
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    deserialize_integer! {
+        deserialize_i8 deserialize_i16 deserialize_i32 deserialize_i64 deserialize_i128
+        deserialize_u8 deserialize_u16 deserialize_u32 deserialize_u64 deserialize_u128
+    }
+
+    forward_to_deserialize_any! {
+        bool f32 f64 char str string bytes byte_buf unit unit_struct seq tuple
+        tuple_struct map struct identifier ignored_any
+    }
+}
+
+// the elements of an array with the indices from 0 to `len`
+struct ArrayAccess<'de> {
+    elements: Peekable<btree_map::IntoIter<usize, AttributeNode<'de>>>,
+    index: usize,
+    len: usize,
+}
+
+impl<'de> SeqAccess<'de> for ArrayAccess<'de> {
+    type Error = Error;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(&mut self, seed: T) -> Result<Option<T::Value>, Error> {
+        if self.index == self.len {
+            return Ok(None);
+        }
+        let index = self.index;
+        self.index += 1;
+        match self.elements.next_if(|(element_index, _)| *element_index == index) {
+            Some((_, node)) => seed.deserialize(NodeDeserializer(node)).map(Some),
+            // indices without an attribute can be read as `None`
+            None => seed.deserialize(UnitDeserializer::new()).map(Some),
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.len - self.index)
+    }
+}
+
+// the fields of a record by their name, or the elements of an array by their index
+struct RecordAccess<'de, I> {
+    fields: I,
+    value: Option<AttributeNode<'de>>,
+}
+
+impl<'de, I, K> MapAccess<'de> for RecordAccess<'de, I>
+where
+    I: Iterator<Item = (K, AttributeNode<'de>)>,
+    K: IntoDeserializer<'de, Error>,
+{
+    type Error = Error;
+
+    fn next_key_seed<S: DeserializeSeed<'de>>(&mut self, seed: S) -> Result<Option<S::Value>, Error> {
+        match self.fields.next() {
+            Some((key, value)) => {
+                self.value = Some(value);
+                seed.deserialize(key.into_deserializer()).map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, Error> {
+        match self.value.take() {
+            Some(value) => seed.deserialize(NodeDeserializer(value)),
+            None => Err(de::Error::custom("value requested before key")),
+        }
+    }
+}
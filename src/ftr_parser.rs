@@ -1,22 +1,24 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::fs::File;
 use std::io::{Cursor, Read, Seek, SeekFrom};
+use std::sync::Arc;
 
 use color_eyre::eyre::bail;
 use lz4_flex::decompress_into;
 use num_bigint::{BigInt, BigUint};
 
-use crate::cbor_decoder::CborDecoder;
-use crate::types::{Attribute, AttributeType, DataType, Event, FTR, Timescale, Transaction, TxGenerator, TxRelation, TxStream};
+use crate::cbor_decoder::{CborDecoder, SkipInput};
+use crate::types::{Attribute, AttributeType, DataType, Event, FTR, RelationChunk, RelationLoading, Timescale, Transaction, TxBlock, TxGenerator, TxLocation, TxRelation, TxStream};
 
-const INFO_CHUNK: u64 = 6;
-const DICTIONARY_CHUNK_UNCOMP: u64 = 8;
-const DICTIONARY_CHUNK_COMP: u64 = 9;
-const DIRECTORY_CHUNK_UNCOMP: u64 = 10;
-const DIRECTORY_CHUNK_COMP: u64 = 11;
-const TX_BLOCK_CHUNK_UNCOMP: u64 = 12;
-const TX_BLOCK_CHUNK_COMP: u64 = 13;
-const RELATIONSHIP_CHUNK_UNCOMP: u64 = 14;
-const RELATIONSHIP_CHUNK_COMP: u64 = 15;
+pub(crate) const INFO_CHUNK: u64 = 6;
+pub(crate) const DICTIONARY_CHUNK_UNCOMP: u64 = 8;
+pub(crate) const DICTIONARY_CHUNK_COMP: u64 = 9;
+pub(crate) const DIRECTORY_CHUNK_UNCOMP: u64 = 10;
+pub(crate) const DIRECTORY_CHUNK_COMP: u64 = 11;
+pub(crate) const TX_BLOCK_CHUNK_UNCOMP: u64 = 12;
+pub(crate) const TX_BLOCK_CHUNK_COMP: u64 = 13;
+pub(crate) const RELATIONSHIP_CHUNK_UNCOMP: u64 = 14;
+pub(crate) const RELATIONSHIP_CHUNK_COMP: u64 = 15;
 
 const STREAM: u64 = 16;
 const GENERATOR: u64 = 17;
@@ -39,24 +41,91 @@ const POINTER: u8 = 9;
 const STRING: u8 = 10;
 const TIME: u8 = 11;
 
+/// What was found while parsing a single chunk of the input.
+pub(crate) enum Chunk {
+    /// Info, dictionary, directory or relationship chunk; its content was added to the FTR
+    Header,
+    /// Transaction block of the stream with the given id.
+    /// Contains the decoded transactions, or `None` if the block was skipped.
+    TxBlock(usize, Option<Vec<Transaction>>),
+    /// End of the input
+    End,
+}
+
+/// Content of a single chunk, as it was read from the input but not yet decoded.
+pub(crate) enum RawChunk {
+    Info(Payload),
+    Dictionary(Payload),
+    Directory(Payload),
+    /// Transaction block with the position of its content in the input.
+    /// The payload is `None` if the content was skipped.
+    TxBlock { stream_id: usize, start_time: i64, end_time: i64, tx_block_id: (u64, bool), payload: Option<Payload> },
+    /// Relationship chunk with the position of its content in the input.
+    /// The payload is `None` if the content was skipped.
+    Relationships { chunk_id: (u64, bool), payload: Option<Payload> },
+    End,
+}
+
+/// The CBOR encoded content of a chunk, possibly LZ4 compressed.
+pub(crate) struct Payload {
+    pub(crate) bytes: Vec<u8>,
+    pub(crate) uncomp_size: Option<usize>,
+}
+
+impl Payload {
+    pub(crate) fn decoder(self) -> color_eyre::Result<CborDecoder<Cursor<Vec<u8>>>> {
+        let bytes = match self.uncomp_size {
+            Some(uncomp_size) => {
+                let mut buf = vec![0u8; uncomp_size];
+                if let Err(e) = decompress_into(self.bytes.as_slice(), &mut buf) {
+                    bail!("Could not decompress compressed data correctly: {}", e)
+                }
+                buf
+            }
+            None => self.bytes,
+        };
+        Ok(CborDecoder::new(Cursor::new(bytes)))
+    }
+}
+
 pub struct FtrParser<'a> {
     ftr: &'a mut FTR,
+    // ids of the transactions stored by this parser, whose relations have to be added, see `update_relations()`
+    new_tx_ids: BTreeSet<usize>,
 }
 
 impl <'a> FtrParser<'a>{
 
     pub fn new(ftr: &'a mut FTR) -> FtrParser<'a>{
-        Self {ftr}
+        Self {ftr, new_tx_ids: BTreeSet::new()}
     }
 
-    pub(super) fn load<R: Read + Seek>(&mut self, file: R) -> color_eyre::Result<()> {
+    pub(super) fn load<R: SkipInput>(&mut self, file: R) -> color_eyre::Result<()> {
         let cbor_decoder = CborDecoder::new(file);
         Self::parse_input(self, cbor_decoder)?;
         Ok(())
     }
 
-    //TODO change to work with buffered readers
-    fn parse_input<R: Read + Seek>(&mut self, mut cbor_decoder: CborDecoder<R>) -> color_eyre::Result<()>{
+    fn parse_input<R: SkipInput>(&mut self, mut cbor_decoder: CborDecoder<R>) -> color_eyre::Result<()>{
+        Self::parse_header(&mut cbor_decoder)?;
+        loop {
+            match Self::parse_chunk(self, &mut cbor_decoder)? {
+                Chunk::TxBlock(stream_id, Some(transactions)) => {
+                    let block_idx = self.ftr.tx_streams.get(&stream_id).unwrap().tx_blocks.len() - 1;
+                    Self::store_transactions(self, stream_id, block_idx, transactions);
+                }
+                Chunk::End => break,
+                _ => {}
+            }
+        }
+        let loaded_streams: Vec<usize> = self.ftr.tx_streams.values().filter(|stream| stream.transactions_loaded).map(|stream| stream.id).collect();
+        for stream_id in loaded_streams {
+            self.ftr.index_stream(stream_id);
+        }
+        Ok(())
+    }
+
+    pub(crate) fn parse_header<R: Read>(cbor_decoder: &mut CborDecoder<R>) -> color_eyre::Result<()>{
         let tag = cbor_decoder.read_tag()?;
         if tag != 55799 {
             bail!("Not a valid FTR file");
@@ -65,142 +134,87 @@ impl <'a> FtrParser<'a>{
         if array_length != -1 {
            bail!("Array does not have indefinite length. Not a valid FTR file!");
         }
-        let mut next= cbor_decoder.peek();
-        while next.is_ok() && next.unwrap() != 0xff {
-            let tag = cbor_decoder.read_tag()?;
-
-            match tag as u64 {
-                INFO_CHUNK => {
-                    let mut cbd: CborDecoder<Cursor<Vec<u8>>> = CborDecoder::new(Cursor::new(cbor_decoder.read_byte_string()?));
-                    let size = cbd.read_array_length()?;
-                    if size != 2 {
-                        bail!("Info Chunk has wrong length. Not a valid FTR file!");
-                    }
-
-                    let time_scale = cbd.read_int()?;
-                    self.ftr.time_scale = Timescale::get_timescale(time_scale);
-
-                    let epoch_tag = cbd.read_tag()?;
-                    if epoch_tag != 1 {
-                        bail!("Wrong epoch tag. Not a valid FTR file!");
-                    }
-                    let _creation_time = cbd.read_int()?;
-                }
-                DICTIONARY_CHUNK_UNCOMP => {
-                    let mut cbd: CborDecoder<Cursor<Vec<u8>>> = CborDecoder::new(Cursor::new(cbor_decoder.read_byte_string()?));
-                    Self::parse_dict(self, &mut cbd)?;
-                }
-
-                DICTIONARY_CHUNK_COMP => {
-                    let len = cbor_decoder.read_array_length()?;
-                    if len != 2 {
-                        bail!("Dictionary Chunk has wrong size. Not a valid FTR file!");
-                    }
-                    let size = cbor_decoder.read_int()?; // uncompressed size
-                    let bytes = cbor_decoder.read_byte_string()?;
-
-                    let mut buf = vec![0u8; size as usize];
-                    decompress_into(bytes.as_slice(), &mut buf)?;
+        Ok(())
+    }
 
-                    Self::parse_dict(self, &mut CborDecoder::new(Cursor::new(buf)))?;
-                }
+    /// Parses the next chunk of the input.
+    /// Transaction blocks are only decoded if the FTR is not backed by a file, otherwise they get skipped
+    /// and can be loaded later on with `load_transactions()`.
+    /// The same goes for relationship chunks, unless the relations of the FTR have already been loaded.
+    pub(crate) fn parse_chunk<R: SkipInput>(&mut self, cbor_decoder: &mut CborDecoder<R>) -> color_eyre::Result<Chunk>{
+        let load_transactions = self.ftr.path.is_none();
+        let load_relations = load_transactions || self.ftr.relation_loading == RelationLoading::All;
+        let raw_chunk = read_chunk(cbor_decoder, load_transactions, load_relations)?;
+        Self::apply_chunk(self, raw_chunk)
+    }
 
-                DIRECTORY_CHUNK_UNCOMP => {
-                    let mut cbd = CborDecoder::new(Cursor::new(cbor_decoder.read_byte_string()?));
-                    Self::parse_dir(self, &mut cbd)?;
+    /// Decodes a chunk that has been read from the input and adds its content to the FTR.
+    pub(crate) fn apply_chunk(&mut self, raw_chunk: RawChunk) -> color_eyre::Result<Chunk>{
+        match raw_chunk {
+            RawChunk::Info(payload) => {
+                let mut cbd = payload.decoder()?;
+                let size = cbd.read_array_length()?;
+                if size != 2 {
+                    bail!("Info Chunk has wrong length. Not a valid FTR file!");
                 }
 
-                DIRECTORY_CHUNK_COMP => {
-                    let size = cbor_decoder.read_array_length()?;
-                    if size != 2 {
-                        bail!("Directory Chunk has wrong size. Not a valid FTR file!");
-                    }
-
-                    let uncomp_size: usize = cbor_decoder.read_int()? as usize;
-                    let mut buf = vec![0u8; uncomp_size];
-                    let bytes = cbor_decoder.read_byte_string()?;
-                    decompress_into(bytes.as_slice(), &mut buf)?;
-
-                    Self::parse_dir(self, &mut CborDecoder::new(Cursor::new(buf)))?;
-                  }
-
-                TX_BLOCK_CHUNK_UNCOMP => {
-                    let len = cbor_decoder.read_array_length()?;
-                    if len != 4 {
-                        bail!("Transaction Block Chunk has wrong size. Not a valid FTR file!");
-                    }
-
-                    let stream_id = cbor_decoder.read_int()? as usize;
-                    let _start_time = cbor_decoder.read_int()?; // start time of block
-                    let end_time = cbor_decoder.read_int()?; // end time of block
-                    if BigInt::from(end_time) > self.ftr.max_timestamp {
-                        self.ftr.max_timestamp = BigInt::from(end_time);
-                    }
+                let time_scale = cbd.read_int()?;
+                self.ftr.time_scale = Timescale::get_timescale(time_scale);
 
-                    self.ftr.tx_streams.get_mut(&stream_id).unwrap().tx_block_ids.push((cbor_decoder.input_stream.stream_position()?, false));
-
-                    if self.ftr.path.is_none() {
-                        let mut cbd = CborDecoder::new(Cursor::new(cbor_decoder.read_byte_string()?));
-                        Self::parse_tx_block(self, &mut cbd)?;
-                        self.ftr.tx_streams.get_mut(&stream_id).unwrap().transactions_loaded = true;
-                    } else {
-                        cbor_decoder.skip_byte_string()?;  // we don't want to load the transactions right now, so we just skip this whole block
-                    }
+                let epoch_tag = cbd.read_tag()?;
+                if epoch_tag != 1 {
+                    bail!("Wrong epoch tag. Not a valid FTR file!");
                 }
+                let _creation_time = cbd.read_int()?;
+            }
 
-                TX_BLOCK_CHUNK_COMP => {
-                    let len = cbor_decoder.read_array_length()?;
-                    if len != 5 {
-                        bail!("Transaction Block Chunk has wrong size. Not a valid FTR file!");
-                    }
-
-                    let stream_id = cbor_decoder.read_int()? as usize;
-                    let _start_time = cbor_decoder.read_int()?; // start time of block
-                    let end_time = cbor_decoder.read_int()?; // end time of block
+            RawChunk::Dictionary(payload) => {
+                Self::parse_dict(self, &mut payload.decoder()?)?;
+            }
 
-                    if BigInt::from(end_time) > self.ftr.max_timestamp {
-                        self.ftr.max_timestamp = BigInt::from(end_time);
-                    }
+            RawChunk::Directory(payload) => {
+                Self::parse_dir(self, &mut payload.decoder()?)?;
+            }
 
-                    self.ftr.tx_streams.get_mut(&stream_id).unwrap().tx_block_ids.push((cbor_decoder.input_stream.stream_position()?, true));
-                    let _uncomp_size = cbor_decoder.read_int();
+            RawChunk::TxBlock { stream_id, start_time, end_time, tx_block_id, payload } => {
+                // the FTR only gets updated once the whole block has been read
+                let transactions = match payload {
+                    Some(payload) => Some(Self::parse_tx_block(self, &mut payload.decoder()?)?),
+                    None => None,
+                };
 
-                    if self.ftr.path.is_none() {
-                        let mut cbd = CborDecoder::new(Cursor::new(cbor_decoder.read_byte_string()?));
-                        Self::parse_tx_block(self, &mut cbd)?;
-                        self.ftr.tx_streams.get_mut(&stream_id).unwrap().transactions_loaded = true;
-                    } else {
-                        cbor_decoder.skip_byte_string()?;
-                    }
-                }
+                self.add_tx_block(stream_id, start_time, end_time, tx_block_id)?;
+                return Ok(Chunk::TxBlock(stream_id, transactions));
+            }
 
-                RELATIONSHIP_CHUNK_UNCOMP => {
-                    let mut cbd = CborDecoder::new(Cursor::new(cbor_decoder.read_byte_string()?));
-                    Self::parse_rel(self, &mut cbd)?;
+            RawChunk::Relationships { chunk_id, payload } => {
+                self.ftr.relation_chunks.push(RelationChunk { position: chunk_id.0, compressed: chunk_id.1, tx_ids: None });
+                if let Some(payload) = payload {
+                    let chunk_idx = self.ftr.relation_chunks.len() - 1;
+                    Self::add_relations(self, chunk_idx, payload, true)?;
                 }
+            }
 
-                RELATIONSHIP_CHUNK_COMP => {
-                    let len = cbor_decoder.read_array_length()?;
-                    if len != 2 {
-                        bail!("Relationship Chunk has wrong size. Not a valid FTR file.");
-                    }
-                    let uncomp_size = cbor_decoder.read_int()?;
-                    let mut buf = vec![0u8; uncomp_size as usize];
-                    let bytes = cbor_decoder.read_byte_string()?;
-                    decompress_into(bytes.as_slice(), &mut buf)?;
-
-                    Self::parse_rel(self, &mut CborDecoder::new(Cursor::new(buf)))?;
-                   }
+            RawChunk::End => return Ok(Chunk::End),
+        }
+        Ok(Chunk::Header)
+    }
 
-                _ => {bail!("Not a valid Tag!")}
-            }
+    fn add_tx_block(&mut self, stream_id: usize, start_time: i64, end_time: i64, tx_block_id: (u64, bool)) -> color_eyre::Result<()>{
+        let stream = match self.ftr.tx_streams.get_mut(&stream_id) {
+            Some(stream) => stream,
+            None => bail!("Transaction Block belongs to unknown stream {stream_id}"),
+        };
+        stream.tx_blocks.push(TxBlock::new(tx_block_id.0, tx_block_id.1, start_time, end_time));
+        stream.transactions_loaded = false;
 
-            next = cbor_decoder.peek();
+        if BigInt::from(end_time) > self.ftr.max_timestamp {
+            self.ftr.max_timestamp = BigInt::from(end_time);
         }
         Ok(())
     }
 
-    fn parse_dict<R: Read + Seek>(&mut self, cbd: &mut CborDecoder<R>) -> color_eyre::Result<()>{
+    fn parse_dict<R: Read>(&mut self, cbd: &mut CborDecoder<R>) -> color_eyre::Result<()>{
         let size = cbd.read_map_length()?;
 
         for _i in 0..size {
@@ -211,7 +225,7 @@ impl <'a> FtrParser<'a>{
         Ok(())
     }
 
-    fn parse_dir<R: Read + Seek>(&mut self, cbd: &mut CborDecoder<R>) -> color_eyre::Result<()>{
+    fn parse_dir<R: Read>(&mut self, cbd: &mut CborDecoder<R>) -> color_eyre::Result<()>{
         let size = cbd.read_array_length()?;
         if size < 0 {
             let mut next_dir = cbd.peek();
@@ -229,7 +243,7 @@ impl <'a> FtrParser<'a>{
     }
 
 
-    fn parse_dir_entry<R: Read + Seek>(&mut self, cbd: &mut CborDecoder<R>) -> color_eyre::Result<()>{
+    fn parse_dir_entry<R: Read>(&mut self, cbd: &mut CborDecoder<R>) -> color_eyre::Result<()>{
         let dir_tag = cbd.read_tag()?;
         if dir_tag == STREAM as i64{
             let len = cbd.read_array_length()?;
@@ -249,6 +263,7 @@ impl <'a> FtrParser<'a>{
                 Some(k) => k,
                 None => bail!("There is not entry in the Dictionary for id {kind_id}"),
             };
+            self.ftr.used_dict_ids.extend([name_id, kind_id]);
 
             self.ftr.tx_streams.insert(stream_id, TxStream{
                 id: stream_id,
@@ -256,7 +271,8 @@ impl <'a> FtrParser<'a>{
                 kind: kind.clone(),
                 generators: vec![],
                 transactions_loaded: false,
-                tx_block_ids: vec![]});
+                tx_blocks: vec![]});
+            self.ftr.scopes.add_stream(stream_id, name);
 
         } else if dir_tag == GENERATOR as i64{
             let len = cbd.read_array_length()?;
@@ -273,16 +289,23 @@ impl <'a> FtrParser<'a>{
             };
 
             let stream_id = cbd.read_int()? as usize;
+            self.ftr.used_dict_ids.insert(name_id);
 
+            // a generator of an unknown stream is kept on its own and reported by `FTR::lint()`
+            if let Some(stream) = self.ftr.tx_streams.get_mut(&stream_id) {
+                stream.generators.push(gen_id);
+                self.ftr.scopes.add_generator(gen_id, &stream.name, name);
+            }
             let generator = TxGenerator{id: gen_id, name: name.clone(), stream_id, transactions: vec![]};
-
             self.ftr.tx_generators.insert(gen_id, generator);
-            self.ftr.tx_streams.get_mut(&stream_id).unwrap().generators.push(gen_id);
         }
         Ok(())
     }
 
-    fn parse_tx_block<R: Read + Seek>(&mut self, cbd: &mut CborDecoder<R>) -> color_eyre::Result<()>{
+    pub(crate) fn parse_tx_block<R: Read>(&mut self, cbd: &mut CborDecoder<R>) -> color_eyre::Result<Vec<Transaction>>{
+        let mut transactions = vec![];
+        self.ftr.relation_index.update(&self.ftr.tx_relations);
+
         let size = cbd.read_array_length()?;
         if size != -1 {
             bail!("Transaction Block does not have indefinite length!");
@@ -294,7 +317,7 @@ impl <'a> FtrParser<'a>{
             let arr_len = cbd.read_array_length()?;
 
             let mut event = Event::new();
-            let mut attributes = vec![Attribute::new_empty(); 0];
+            let mut attributes: Vec<Attribute> = vec![];
 
             for _i in 0..arr_len {
                 let tag = cbd.read_tag()?;
@@ -353,23 +376,22 @@ impl <'a> FtrParser<'a>{
                 out_relations: vec![],
             };
 
-            for rel in &self.ftr.tx_relations {
-                if rel.source_tx_id == tx.event.tx_id {
-                    tx.out_relations.push(rel.clone());
-                } else if rel.sink_tx_id == tx.event.tx_id {
-                    tx.inc_relations.push(rel.clone());
-                }
+            if let Some(gen) = self.ftr.tx_generators.get(&tx.event.gen_id) {
+                self.ftr.relation_index.link(&mut self.ftr.tx_relations, &mut tx, gen.stream_id);
             }
 
-            self.ftr.tx_generators.get_mut(&tx.event.gen_id).unwrap().transactions.push(tx);
+            transactions.push(tx);
 
             next_tx = cbd.peek();
 
         }
-        Ok(())
+        Ok(transactions)
     }
 
-    fn parse_rel<R: Read + Seek>(&mut self, cbd: &mut CborDecoder<R>) -> color_eyre::Result<()>{
+    // decodes the relations that `keep` selects by their position in the chunk, their source and their sink
+    fn parse_rel<R: Read>(&mut self, cbd: &mut CborDecoder<R>, mut keep: impl FnMut(usize, usize, usize) -> bool) -> color_eyre::Result<Vec<(usize, TxRelation)>>{
+        let mut relations = vec![];
+        let mut idx = 0;
         let size = cbd.read_array_length()?;
         if size != -1 {
             bail!("Relation block does not have indefinite size!");
@@ -384,58 +406,133 @@ impl <'a> FtrParser<'a>{
             let type_id = cbd.read_int()? as usize;
             let from_tx_id = cbd.read_int()? as usize;
             let to_tx_id = cbd.read_int()? as usize;
-            let from_stream_id = if sz > 3 {cbd.read_int()? as usize} else {
-                let mut stream_id: usize = 0;
-                for curr_gen in &self.ftr.tx_generators {
-                    let mut tx: Option<&Transaction> = None;
-                    for curr_tx in &curr_gen.1.transactions {
-                        if curr_tx.event.tx_id == from_tx_id {
-                            tx = Some(curr_tx);
-                            break;
-                        }
-                    }
-                    if tx.is_some() && &tx.unwrap().event.gen_id == curr_gen.0 {
-                        stream_id = curr_gen.1.stream_id;
-                        break;
-                    }
-                }
-                stream_id
-            };
-            let to_stream_id = if sz > 3 {cbd.read_int()? as usize} else {
-                let mut stream_id: usize = 0;
-                for curr_gen in &self.ftr.tx_generators {
-                    let mut tx: Option<&Transaction> = None;
-                    for curr_tx in &curr_gen.1.transactions {
-                        if curr_tx.event.tx_id == to_tx_id {
-                            tx = Some(curr_tx);
-                            break;
-                        }
-                    }
-                    if tx.is_some() && &tx.unwrap().event.gen_id == curr_gen.0 {
-                        stream_id = curr_gen.1.stream_id;
-                        break;
-                    }
+            // relations with 3 elements don't contain the streams, these are filled in once the transactions get loaded
+            let from_stream_id = if sz > 3 {Some(cbd.read_int()? as usize)} else {None};
+            let to_stream_id = if sz > 3 {Some(cbd.read_int()? as usize)} else {None};
+            idx += 1;
+            self.ftr.used_dict_ids.insert(type_id);
+            if !keep(idx - 1, from_tx_id, to_tx_id) {
+                next_rel = cbd.peek();
+                continue;
+            }
+            let rel_name = match self.ftr.relation_names.get(&type_id) {
+                Some(name) => name.clone(),
+                None => {
+                    let name: Arc<str> = match self.ftr.str_dict.get(&type_id) {
+                        Some(name) => name.as_str().into(),
+                        None => bail!("There is not entry in the Dictionary for id {type_id}"),
+                    };
+                    self.ftr.relation_names.insert(type_id, name.clone());
+                    name
                 }
-                stream_id
-
             };
-            let rel_name = self.ftr.str_dict.get(&type_id).unwrap();
 
             let tx_relation = TxRelation{
-                name: rel_name.clone(),
+                name: rel_name,
                 source_tx_id: from_tx_id,
                 sink_tx_id: to_tx_id,
                 source_stream_id: from_stream_id,
                 sink_stream_id: to_stream_id,
             };
 
-            self.ftr.tx_relations.push(tx_relation);
+            relations.push((idx - 1, tx_relation));
 
             next_rel = cbd.peek();
         }
+        Ok(relations)
+    }
+
+    /// Decodes a relationship chunk and adds the relations that are not in `tx_relations` yet to the FTR:
+    /// all of them if `all` is set, otherwise those from or to a transaction that was stored by this parser.
+    pub(crate) fn add_relations(&mut self, chunk_idx: usize, payload: Payload, all: bool) -> color_eyre::Result<()>{
+        let mut cbd = payload.decoder()?;
+        let new_tx_ids = std::mem::take(&mut self.new_tx_ids);
+        let added_relations = std::mem::take(&mut self.ftr.added_relations);
+        let mut tx_ids: Option<(usize, usize)> = None;
+        let relations = Self::parse_rel(self, &mut cbd, |idx, source, sink| {
+            let (first, last) = tx_ids.unwrap_or((source, source));
+            tx_ids = Some((first.min(source).min(sink), last.max(source).max(sink)));
+            (all || new_tx_ids.contains(&source) || new_tx_ids.contains(&sink)) && !added_relations.contains(&(chunk_idx, idx))
+        });
+        self.new_tx_ids = new_tx_ids;
+        self.ftr.added_relations = added_relations;
+
+        let first = self.ftr.tx_relations.len();
+        for (idx, relation) in relations? {
+            // once all relations are loaded, there is nothing left to add
+            if !all {
+                self.ftr.added_relations.insert((chunk_idx, idx));
+            }
+            self.ftr.tx_relations.push(relation);
+        }
+        self.ftr.relation_chunks[chunk_idx].tx_ids = tx_ids;
+        connect_new_relations(self.ftr, first);
         Ok(())
     }
 
+    /// Relationship chunks that may contain relations of the transactions stored by this parser, which have not been
+    /// added yet, with the position of their content and whether it is compressed.
+    /// Chunks that have been decoded before are only read again if their id range contains such a transaction.
+    pub(crate) fn relation_chunks_to_read(&self) -> Vec<(usize, (u64, bool))> {
+        if self.ftr.relations_loaded() || self.new_tx_ids.is_empty() {
+            return vec![];
+        }
+        self.ftr.relation_chunks
+            .iter()
+            .enumerate()
+            .filter(|(_, chunk)| chunk.tx_ids.is_none_or(|(first, last)| self.new_tx_ids.range(first..=last).next().is_some()))
+            .map(|(chunk_idx, chunk)| (chunk_idx, (chunk.position, chunk.compressed)))
+            .collect()
+    }
+
+    /// Relationship chunks with the position of their content and whether it is compressed, to load all relations.
+    pub(crate) fn all_relation_chunks(&self) -> Vec<(usize, (u64, bool))> {
+        self.ftr.relation_chunks.iter().map(|chunk| (chunk.position, chunk.compressed)).enumerate().collect()
+    }
+
+    /// Marks the relations as loaded, once the relations of `all_relation_chunks()` have been added.
+    pub(crate) fn set_relations_loaded(&mut self) {
+        self.ftr.relation_loading = RelationLoading::All;
+        self.ftr.added_relations = HashSet::new();
+    }
+
+    /// Loads all relations of the file, if this has not happened yet.
+    pub(super) fn load_relations(&mut self) -> color_eyre::Result<()>{
+        if self.ftr.relations_loaded() {
+            return Ok(());
+        }
+        let reader = Self::open_file(self)?;
+        for (chunk_idx, chunk_id) in Self::all_relation_chunks(self) {
+            let payload = read_stored_payload(&reader, chunk_id)?;
+            Self::add_relations(self, chunk_idx, payload, true)?;
+        }
+        Self::set_relations_loaded(self);
+        Ok(())
+    }
+
+    // adds the relations of the transactions that were stored by this parser. The relationship chunks are decoded
+    // one at a time and only the relations that are needed are kept, so the memory stays bounded by the loaded transactions.
+    fn update_relations(&mut self) -> color_eyre::Result<()>{
+        let chunk_ids = Self::relation_chunks_to_read(self);
+        if !chunk_ids.is_empty() {
+            let reader = Self::open_file(self)?;
+            for (chunk_idx, chunk_id) in chunk_ids {
+                let payload = read_stored_payload(&reader, chunk_id)?;
+                Self::add_relations(self, chunk_idx, payload, false)?;
+            }
+        }
+        self.new_tx_ids.clear();
+        Ok(())
+    }
+
+    // the file the FTR was read from, for loading what is not in memory yet
+    fn open_file(&self) -> color_eyre::Result<File>{
+        match &self.ftr.path {
+            Some(path) => Ok(File::open(path)?),
+            None => bail!("The FTR was not read from a file, so transactions and relations that are not in memory cannot be loaded. \nLoad them with the reader the FTR was read with."),
+        }
+    }
+
     //loads the transactions of all generators of stream 'stream_id'
     pub(super) fn load_transactions(&mut self, stream_id: usize) -> color_eyre::Result<()>{
         if self.ftr.path.is_none() {
@@ -443,48 +540,184 @@ impl <'a> FtrParser<'a>{
         }
         let reader = File::open(self.ftr.path.as_ref().unwrap())?;
 
-        let tx_block_ids = self.ftr.tx_streams.get(&stream_id).unwrap().tx_block_ids.clone();
+        // blocks that have been loaded on their own are loaded again, so that the transactions stay in order
+        self.ftr.drop_stream_from_memory(stream_id);
+        let num_blocks = self.ftr.tx_streams.get(&stream_id).unwrap().tx_blocks.len();
 
-        for tx_block_id in tx_block_ids{
+        for block_idx in 0..num_blocks {
+            Self::load_tx_block(self, &reader, stream_id, block_idx)?;
+        }
+        self.ftr.tx_streams.get_mut(&stream_id).unwrap().transactions_loaded = true;
+        self.ftr.index_stream(stream_id);
+        Self::update_relations(self)
+    }
 
-            let mut cbor_decoder = CborDecoder::new(&reader);
+    // loads the transactions of a single transaction block of a stream
+    pub(super) fn load_tx_block(&mut self, reader: &File, stream_id: usize, block_idx: usize) -> color_eyre::Result<()>{
+        let payload = Self::read_tx_block(self, reader, stream_id, block_idx)?;
+        Self::add_transactions(self, stream_id, block_idx, payload)
+    }
 
-            cbor_decoder.input_stream.seek(SeekFrom::Start(tx_block_id.0))?;
+    fn read_tx_block(&self, reader: &File, stream_id: usize, block_idx: usize) -> color_eyre::Result<Payload>{
+        let tx_block = &self.ftr.tx_streams.get(&stream_id).unwrap().tx_blocks[block_idx];
+        read_stored_payload(reader, (tx_block.position, tx_block.compressed))
+    }
 
-            if tx_block_id.1 {
-                let uncomp_size = cbor_decoder.read_int()?;
+    // decodes the content of a transaction block and adds the transactions to their generators
+    pub(crate) fn add_transactions(&mut self, stream_id: usize, block_idx: usize, payload: Payload) -> color_eyre::Result<()>{
+        let transactions = Self::parse_tx_block(self, &mut payload.decoder()?)?;
+        Self::store_transactions(self, stream_id, block_idx, transactions);
+        Ok(())
+    }
 
-                let mut buf = vec![0u8; uncomp_size as usize];
-                let bytes = cbor_decoder.read_byte_string()?;
-                match decompress_into(bytes.as_slice(), &mut buf) {
-                    Ok(_) => {}
-                    Err(e) => {bail!("Could not decompress compressed data correctly: {}", e)}
-                }
+    // adds the transactions of a block to their generators and to the transaction index, behind the transactions
+    // of the loaded blocks before it, so that the generators are in the same order as if the whole stream was loaded.
+    // The interval indices are built afterwards with 'FTR::index_stream()', once all blocks of the load are stored.
+    fn store_transactions(&mut self, stream_id: usize, block_idx: usize, transactions: Vec<Transaction>) {
+        let stream = self.ftr.tx_streams.get_mut(&stream_id).unwrap();
+        let tx_block = &mut stream.tx_blocks[block_idx];
+        tx_block.set_content(&transactions);
+        tx_block.loaded = true;
+        stream.transactions_loaded = stream.tx_blocks.iter().all(|b| b.loaded);
+
+        let mut offsets: HashMap<usize, usize> = HashMap::new();
+        for tx_block in stream.tx_blocks[..block_idx].iter().filter(|b| b.loaded) {
+            for (gen_id, count) in &tx_block.gen_tx_counts {
+                *offsets.entry(*gen_id).or_default() += count;
+            }
+        }
+        let mut by_generator: BTreeMap<usize, Vec<Transaction>> = BTreeMap::new();
+        for tx in transactions {
+            self.new_tx_ids.insert(tx.event.tx_id);
+            by_generator.entry(tx.event.gen_id).or_default().push(tx);
+        }
 
-                Self::parse_tx_block(self, &mut CborDecoder::new(Cursor::new(buf)))?;
-            } else {
-                Self::parse_tx_block(self, &mut CborDecoder::new(Cursor::new(cbor_decoder.read_byte_string()?)))?;
+        for (gen_id, transactions) in by_generator {
+            for tx in &transactions {
+                self.ftr.add_attribute_slots(tx);
+            }
+            let gen = self.ftr.tx_generators.get_mut(&gen_id).unwrap();
+            let at = offsets.get(&gen_id).map_or(0, |offset| (*offset).min(gen.transactions.len()));
+            gen.transactions.splice(at..at, transactions);
+            // transactions of later blocks that are already loaded move back
+            for (position, tx) in gen.transactions.iter().enumerate().skip(at) {
+                self.ftr.tx_index.insert(tx.event.tx_id, TxLocation{
+                    stream_id: gen.stream_id,
+                    gen_id,
+                    position,
+                });
             }
         }
-        self.ftr.tx_streams.get_mut(&stream_id).unwrap().transactions_loaded = true;
+    }
+
+    /// Loads the transaction block that contains the transaction with id `tx_id`, if it is not loaded yet.
+    /// If the stream of the transaction is known, only the blocks of this stream are searched.
+    ///
+    /// Writers usually hand out transaction ids in the order of the start times, so the blocks decoded so far bound
+    /// the start time of the transaction: it starts no earlier than a transaction with a smaller id and no later than
+    /// one with a larger id. Blocks whose time span lies within these bounds are decoded first, the one in the middle
+    /// first, which narrows the bounds. The format doesn't guarantee this order though, as begin times can be given
+    /// explicitly, so once no block within the bounds is left, the remaining blocks that might contain the
+    /// transaction are decoded as well.
+    pub(super) fn load_block_of_transaction(&mut self, tx_id: usize, stream_id: Option<usize>) -> color_eyre::Result<()>{
+        if self.ftr.tx_index.contains_key(&tx_id) {
+            return Ok(());
+        }
+        // without a file, every transaction is in memory, unless the FTR comes from an `AsyncFtrReader`
+        if self.ftr.path.is_none() && self.ftr.tx_streams.values().flat_map(|stream| &stream.tx_blocks).all(|b| b.loaded) {
+            return Ok(());
+        }
+        let reader = Self::open_file(self)?;
+
+        let mut stream_ids: Vec<usize> = match stream_id {
+            Some(stream_id) if self.ftr.tx_streams.contains_key(&stream_id) => vec![stream_id],
+            _ => self.ftr.tx_streams.keys().cloned().collect(),
+        };
+        stream_ids.sort();
+
+        let mut searched = HashSet::new();
+        while let Some((stream_id, block_idx)) = Self::next_block_to_search(self, tx_id, &stream_ids, &searched) {
+            searched.insert((stream_id, block_idx));
+            let payload = Self::read_tx_block(self, &reader, stream_id, block_idx)?;
+            let transactions = Self::parse_tx_block(self, &mut payload.decoder()?)?;
+            if transactions.iter().any(|tx| tx.event.tx_id == tx_id) {
+                Self::store_transactions(self, stream_id, block_idx, transactions);
+                self.ftr.index_stream(stream_id);
+                return Self::update_relations(self);
+            }
+            self.ftr.tx_streams.get_mut(&stream_id).unwrap().tx_blocks[block_idx].set_content(&transactions);
+        }
         Ok(())
     }
 
-    fn parse_attribute<R: Read + Seek>(&self, cbd: &mut CborDecoder<R>, attribute_type: u64) -> color_eyre::Result<Attribute> {
+    // the next block that might contain the transaction. Decoded blocks whose id range contains it come first,
+    // as the id ranges of blocks from different streams overlap, a block might not contain the transaction after all.
+    // Otherwise it is the middle one of the blocks that have not been decoded and lie within the bounds of its start time,
+    // and at last any block that has not been searched and whose id range is unknown or contains the transaction.
+    fn next_block_to_search(&self, tx_id: usize, stream_ids: &[usize], searched: &HashSet<(usize, usize)>) -> Option<(usize, usize)> {
+        let mut lower: Option<&BigUint> = None;
+        let mut upper: Option<&BigUint> = None;
+        for tx_block in self.ftr.tx_streams.values().flat_map(|stream| &stream.tx_blocks) {
+            if let (Some((first, last)), Some((first_start, last_start))) = (tx_block.tx_ids, &tx_block.tx_id_starts) {
+                if first < tx_id && lower.is_none_or(|lower| first_start > lower) {
+                    lower = Some(first_start);
+                }
+                if last > tx_id && upper.is_none_or(|upper| last_start < upper) {
+                    upper = Some(last_start);
+                }
+            }
+        }
+        let within_bounds = |start: &BigUint, end: &BigUint| lower.is_none_or(|lower| end >= lower) && upper.is_none_or(|upper| start <= upper);
+
+        let mut unknown_blocks = vec![];
+        let mut out_of_bounds = None;
+        for stream_id in stream_ids {
+            for (block_idx, tx_block) in self.ftr.tx_streams.get(stream_id).unwrap().tx_blocks.iter().enumerate() {
+                if tx_block.loaded || searched.contains(&(*stream_id, block_idx)) {
+                    continue;
+                }
+                match (tx_block.tx_ids, &tx_block.tx_times) {
+                    (Some((first, last)), Some((start, end))) if first <= tx_id && tx_id <= last => {
+                        if within_bounds(start, end) {
+                            return Some((*stream_id, block_idx));
+                        }
+                        out_of_bounds.get_or_insert((*stream_id, block_idx));
+                    }
+                    (None, _) => {
+                        let start = BigUint::try_from(tx_block.start_time).unwrap_or_default();
+                        let end = BigUint::try_from(tx_block.end_time).unwrap_or_default();
+                        if within_bounds(&start, &end) {
+                            unknown_blocks.push((start, *stream_id, block_idx));
+                        } else {
+                            out_of_bounds.get_or_insert((*stream_id, block_idx));
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+        unknown_blocks.sort();
+        unknown_blocks
+            .get(unknown_blocks.len() / 2)
+            .map(|(_, stream_id, block_idx)| (*stream_id, *block_idx))
+            .or(out_of_bounds)
+    }
+
+    fn parse_attribute<R: Read>(&mut self, cbd: &mut CborDecoder<R>, attribute_type: u64) -> color_eyre::Result<Attribute> {
         let name_id = cbd.read_int()? as usize;
         let data_type = cbd.read_int()?;
         let data_type_with_value = match data_type as u8 {
             BOOLEAN => DataType::Boolean(cbd.read_boolean()?),
-            ENUMERATION => DataType::Enumeration(self.ftr.str_dict.get(&(cbd.read_int()? as usize)).unwrap().clone()),
+            ENUMERATION => DataType::Enumeration(self.dict_entry(cbd.read_int()? as usize)),
             INTEGER => DataType::Integer(cbd.read_int()?),
             UNSIGNED => DataType::Unsigned(cbd.read_int()? as u64),
             FLOATING_POINT_NUMBER => DataType::FloatingPointNumber(cbd.read_float()?),
-            BIT_VECTOR => DataType::BitVector(self.ftr.str_dict.get(&(cbd.read_int()? as usize)).unwrap().clone()),
-            LOGIC_VECTOR => DataType::LogicVector(self.ftr.str_dict.get(&(cbd.read_int()? as usize)).unwrap().clone()),
+            BIT_VECTOR => DataType::BitVector(self.dict_entry(cbd.read_int()? as usize)),
+            LOGIC_VECTOR => DataType::LogicVector(self.dict_entry(cbd.read_int()? as usize)),
             FIXED_POINT_INTEGER => DataType::FixedPointInteger(cbd.read_float()?),
             UNSIGNED_FIXED_POINT_INTEGER => DataType::UnsignedFixedPointInteger(cbd.read_float()?),
             POINTER => DataType::Pointer(cbd.read_int()? as u64),
-            STRING => DataType::String(self.ftr.str_dict.get(&(cbd.read_int()? as usize)).unwrap().clone()),
+            STRING => DataType::String(self.dict_entry(cbd.read_int()? as usize)),
             TIME => DataType::Time(cbd.read_int()? as u64),
             _ => DataType::Error,
         };
@@ -498,23 +731,162 @@ impl <'a> FtrParser<'a>{
 
         Ok(Attribute{
             kind,
-            name: self.ftr.str_dict.get(&name_id).unwrap().clone(),
+            name: self.dict_entry(name_id),
             data_type: data_type_with_value,
         })
     }
+
+    // the dictionary entry with the given id, which is remembered as used for `FTR::lint()`
+    fn dict_entry(&mut self, id: usize) -> String {
+        self.ftr.used_dict_ids.insert(id);
+        self.ftr.str_dict.get(&id).unwrap().clone()
+    }
 }
 
-// TODO please improve this
-pub(super) fn connect_relations_and_transactions(ftr: &mut FTR) {
-    for gen in ftr.tx_generators.values_mut() {
-        for tx in gen.transactions.iter_mut() {
-            for rel in &ftr.tx_relations {
-                if rel.source_tx_id == tx.event.tx_id {
-                    tx.out_relations.push(rel.clone());
-                } else if rel.sink_tx_id == tx.event.tx_id {
-                    tx.inc_relations.push(rel.clone());
-                }
+/// What kind of chunk a header belongs to.
+pub(crate) enum ChunkKind {
+    Info,
+    Dictionary,
+    Directory,
+    TxBlock { stream_id: usize, start_time: i64, end_time: i64 },
+    Relationships,
+}
+
+/// Everything of a chunk that comes before its content.
+pub(crate) struct ChunkHeader {
+    pub(crate) kind: ChunkKind,
+    /// Position in the input that is stored for transaction blocks and relationship chunks to read their content later,
+    /// see `read_content_head()`
+    pub(crate) content_position: u64,
+    pub(crate) compressed: bool,
+    pub(crate) uncomp_size: Option<usize>,
+    /// Number of bytes of the content, which directly follows the header
+    pub(crate) len: u64,
+}
+
+impl ChunkHeader {
+    /// Whether the content has to be read. Info, dictionary and directory chunks are always read.
+    pub(crate) fn content_needed(&self, load_transactions: bool, load_relations: bool) -> bool {
+        match self.kind {
+            ChunkKind::TxBlock { .. } => load_transactions,
+            ChunkKind::Relationships => load_relations,
+            _ => true,
+        }
+    }
+
+    /// Turns the header and the content, if it has been read, into a chunk.
+    pub(crate) fn into_raw_chunk(self, bytes: Option<Vec<u8>>) -> RawChunk {
+        let payload = bytes.map(|bytes| Payload{bytes, uncomp_size: self.uncomp_size});
+        let chunk_id = (self.content_position, self.compressed);
+        match (self.kind, payload) {
+            (ChunkKind::TxBlock { stream_id, start_time, end_time }, payload) => RawChunk::TxBlock { stream_id, start_time, end_time, tx_block_id: chunk_id, payload },
+            (ChunkKind::Relationships, payload) => RawChunk::Relationships { chunk_id, payload },
+            (ChunkKind::Info, Some(payload)) => RawChunk::Info(payload),
+            (ChunkKind::Dictionary, Some(payload)) => RawChunk::Dictionary(payload),
+            (ChunkKind::Directory, Some(payload)) => RawChunk::Directory(payload),
+            (_, None) => unreachable!("the content of info, dictionary and directory chunks is always read"),
+        }
+    }
+}
+
+/// Reads the header of the next chunk, which leaves the input at the start of the content.
+/// Returns `None` at the end of the input. Used by the synchronous and the async reader.
+pub(crate) fn read_chunk_header<R: Read>(cbor_decoder: &mut CborDecoder<R>) -> color_eyre::Result<Option<ChunkHeader>>{
+    let next = cbor_decoder.peek();
+    if next.is_err() || next.unwrap() == 0xff {
+        return Ok(None);
+    }
+    let tag = cbor_decoder.read_tag()? as u64;
+
+    let (kind, compressed) = match tag {
+        INFO_CHUNK => (ChunkKind::Info, false),
+        DICTIONARY_CHUNK_UNCOMP => (ChunkKind::Dictionary, false),
+        DICTIONARY_CHUNK_COMP => {
+            if cbor_decoder.read_array_length()? != 2 {
+                bail!("Dictionary Chunk has wrong size. Not a valid FTR file!");
+            }
+            (ChunkKind::Dictionary, true)
+        }
+        DIRECTORY_CHUNK_UNCOMP => (ChunkKind::Directory, false),
+        DIRECTORY_CHUNK_COMP => {
+            if cbor_decoder.read_array_length()? != 2 {
+                bail!("Directory Chunk has wrong size. Not a valid FTR file!");
+            }
+            (ChunkKind::Directory, true)
+        }
+        TX_BLOCK_CHUNK_UNCOMP | TX_BLOCK_CHUNK_COMP => {
+            let compressed = tag == TX_BLOCK_CHUNK_COMP;
+            let len = cbor_decoder.read_array_length()?;
+            if len != if compressed {5} else {4} {
+                bail!("Transaction Block Chunk has wrong size. Not a valid FTR file!");
+            }
+
+            let stream_id = cbor_decoder.read_int()? as usize;
+            let start_time = cbor_decoder.read_int()?; // start time of block
+            let end_time = cbor_decoder.read_int()?; // end time of block
+            (ChunkKind::TxBlock { stream_id, start_time, end_time }, compressed)
+        }
+        RELATIONSHIP_CHUNK_UNCOMP => (ChunkKind::Relationships, false),
+        RELATIONSHIP_CHUNK_COMP => {
+            if cbor_decoder.read_array_length()? != 2 {
+                bail!("Relationship Chunk has wrong size. Not a valid FTR file.");
+            }
+            (ChunkKind::Relationships, true)
+        }
+        _ => {bail!("Not a valid Tag!")}
+    };
+
+    let content_position = cbor_decoder.position();
+    let (uncomp_size, len) = read_content_head(cbor_decoder, compressed)?;
+    Ok(Some(ChunkHeader{kind, content_position, compressed, uncomp_size, len}))
+}
+
+/// Reads the next chunk from the input without decoding its content.
+/// The content of transaction blocks and relationship chunks is only read if `load_transactions` or `load_relations`
+/// is set, otherwise it gets skipped.
+pub(crate) fn read_chunk<R: SkipInput>(cbor_decoder: &mut CborDecoder<R>, load_transactions: bool, load_relations: bool) -> color_eyre::Result<RawChunk>{
+    let header = match read_chunk_header(cbor_decoder)? {
+        Some(header) => header,
+        None => return Ok(RawChunk::End),
+    };
+    let bytes = if header.content_needed(load_transactions, load_relations) {
+        Some(cbor_decoder.read_bytes(header.len)?)
+    } else {
+        cbor_decoder.skip_bytes(header.len)?;  // transactions and relations are loaded once they are needed
+        None
+    };
+    Ok(header.into_raw_chunk(bytes))
+}
+
+/// Reads the uncompressed size, for compressed content, and the length of the content, which leaves the input at the
+/// start of the content. The content of a stored transaction block or relationship chunk starts with these.
+pub(crate) fn read_content_head<R: Read>(cbor_decoder: &mut CborDecoder<R>, compressed: bool) -> color_eyre::Result<(Option<usize>, u64)>{
+    let uncomp_size = if compressed {Some(cbor_decoder.read_int()? as usize)} else {None};
+    Ok((uncomp_size, cbor_decoder.read_byte_string_length()?))
+}
+
+// reads the content of a transaction block or relationship chunk from the position that was stored for it
+fn read_stored_payload(reader: &File, (position, compressed): (u64, bool)) -> color_eyre::Result<Payload>{
+    let mut cbor_decoder = CborDecoder::new(reader);
+    cbor_decoder.input_stream.seek(SeekFrom::Start(position))?;
+    let (uncomp_size, len) = read_content_head(&mut cbor_decoder, compressed)?;
+    Ok(Payload{bytes: cbor_decoder.read_bytes(len)?, uncomp_size})
+}
+
+// adds the relations from position `first` on to the incoming and outgoing relations of the loaded transactions
+pub(crate) fn connect_new_relations(ftr: &mut FTR, first: usize) {
+    ftr.relation_index.update(&ftr.tx_relations);
+    for (idx, rel) in ftr.tx_relations.iter_mut().enumerate().skip(first) {
+        if let Some(location) = ftr.tx_index.get(&rel.source_tx_id) {
+            rel.source_stream_id.get_or_insert(location.stream_id);
+            ftr.tx_generators.get_mut(&location.gen_id).unwrap().transactions[location.position].out_relations.push(idx);
+        }
+        if let Some(location) = ftr.tx_index.get(&rel.sink_tx_id) {
+            rel.sink_stream_id.get_or_insert(location.stream_id);
+            // relations of a transaction to itself only count as outgoing
+            if rel.source_tx_id != rel.sink_tx_id {
+                ftr.tx_generators.get_mut(&location.gen_id).unwrap().transactions[location.position].inc_relations.push(idx);
             }
         }
     }
-}
\ No newline at end of file
+}
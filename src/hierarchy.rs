@@ -0,0 +1,146 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::types::FTR;
+
+/// Names of the relations that are read as parent/child relations between transactions.
+#[derive(Debug, Clone, Default)]
+pub struct HierarchyOptions {
+    parent_of: Vec<String>,
+    child_of: Vec<String>,
+}
+
+impl HierarchyOptions {
+    /// No relation is part of the hierarchy.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The `parent_of` and `child_of` relations written by LWTR4SC.
+    pub fn lwtr() -> Self {
+        Self::new().parent_of("parent_of").child_of("child_of")
+    }
+
+    /// Relations with this name point from the parent to the child.
+    pub fn parent_of(mut self, name: &str) -> Self {
+        self.parent_of.push(name.to_string());
+        self
+    }
+
+    /// Relations with this name point from the child to the parent.
+    pub fn child_of(mut self, name: &str) -> Self {
+        self.child_of.push(name.to_string());
+        self
+    }
+}
+
+/// Transactions arranged as a tree by their relations, see `FTR::hierarchy()`.
+/// Transactions are referred to by their ids, as their parents and children can be in other streams,
+/// which might not be loaded.
+///
+/// If a transaction has several parents, the first relation decides, and relations that would
+/// close a cycle are left out.
+#[derive(Debug, Clone, Default)]
+pub struct Hierarchy {
+    parents: HashMap<usize, usize>,
+    children: HashMap<usize, Vec<usize>>,
+    // loaded transactions without a parent
+    roots: Vec<usize>,
+}
+
+impl Hierarchy {
+    pub(crate) fn new(ftr: &FTR, options: &HierarchyOptions) -> Self {
+        let mut hierarchy = Hierarchy::default();
+        // each transaction has at most one parent, so the child is the root of its tree when the relation is added,
+        // and the relation closes a cycle exactly if the parent is already in the same tree
+        let mut trees = DisjointSets::default();
+        for rel in &ftr.tx_relations {
+            let (parent, child) = if options.parent_of.iter().any(|name| **name == *rel.name) {
+                (rel.source_tx_id, rel.sink_tx_id)
+            } else if options.child_of.iter().any(|name| **name == *rel.name) {
+                (rel.sink_tx_id, rel.source_tx_id)
+            } else {
+                continue;
+            };
+            if hierarchy.parents.contains_key(&child) || !trees.union(parent, child) {
+                continue;
+            }
+            hierarchy.parents.insert(child, parent);
+            hierarchy.children.entry(parent).or_default().push(child);
+        }
+
+        let mut roots: HashSet<usize> = ftr.tx_index.keys().copied().collect();
+        roots.extend(hierarchy.children.keys().copied());
+        roots.retain(|tx_id| !hierarchy.parents.contains_key(tx_id));
+        hierarchy.roots = roots.into_iter().collect();
+        hierarchy.roots.sort();
+        hierarchy
+    }
+
+    /// Returns the ids of the children of the transaction.
+    pub fn children(&self, tx_id: usize) -> &[usize] {
+        self.children.get(&tx_id).map_or(&[], |children| children.as_slice())
+    }
+
+    /// Returns the id of the parent of the transaction.
+    pub fn parent(&self, tx_id: usize) -> Option<usize> {
+        self.parents.get(&tx_id).copied()
+    }
+
+    /// Returns the ids of the parent, its parent and so on.
+    pub fn ancestors(&self, tx_id: usize) -> impl Iterator<Item = usize> + '_ {
+        std::iter::successors(self.parent(tx_id), |tx_id| self.parent(*tx_id))
+    }
+
+    /// Returns the ids of the transactions without a parent, i.e. the loaded transactions and the parents
+    /// of the hierarchy that are not the child of another transaction, ordered by id.
+    pub fn roots(&self) -> &[usize] {
+        &self.roots
+    }
+
+    /// Visits the transaction and everything below it depth-first, parents before their children.
+    /// Returns the ids together with their depth below `tx_id`.
+    pub fn depth_first(&self, tx_id: usize) -> impl Iterator<Item = (usize, usize)> + '_ {
+        let mut stack = vec![(tx_id, 0)];
+        std::iter::from_fn(move || {
+            let (tx_id, depth) = stack.pop()?;
+            stack.extend(self.children(tx_id).iter().rev().map(|child| (*child, depth + 1)));
+            Some((tx_id, depth))
+        })
+    }
+
+    /// Visits all roots and their descendants depth-first.
+    pub fn walk(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.roots.iter().flat_map(|root| self.depth_first(*root))
+    }
+}
+
+// transactions connected by the relations of the hierarchy so far, ignoring their direction
+#[derive(Default)]
+struct DisjointSets {
+    representatives: HashMap<usize, usize>,
+}
+
+impl DisjointSets {
+    fn find(&mut self, mut tx_id: usize) -> usize {
+        while let Some(&next) = self.representatives.get(&tx_id) {
+            if next == tx_id {
+                break;
+            }
+            // path splitting, lets the transaction point to its grandparent for the next search
+            let after_next = self.representatives.get(&next).copied().unwrap_or(next);
+            self.representatives.insert(tx_id, after_next);
+            tx_id = next;
+        }
+        tx_id
+    }
+
+    // merges the sets of both transactions, returns false if they already are in the same set
+    fn union(&mut self, a: usize, b: usize) -> bool {
+        let (a, b) = (self.find(a), self.find(b));
+        if a == b {
+            return false;
+        }
+        self.representatives.insert(a, b);
+        true
+    }
+}
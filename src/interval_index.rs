@@ -0,0 +1,76 @@
+use num_bigint::BigUint;
+
+use crate::types::Transaction;
+
+/// Transactions of a stream or generator, sorted by their start time.
+/// Every entry also knows the largest end time up to it, so entries that end before a given time can be skipped
+/// without looking at them.
+#[derive(Debug, Default, Clone)]
+pub(crate) struct IntervalIndex {
+    entries: Vec<IntervalEntry>,
+    // largest end time of entries[..=i]
+    max_end: Vec<BigUint>,
+    // the same transactions sorted by their end time
+    by_end: Vec<IntervalEntry>,
+}
+
+#[derive(Debug, Clone)]
+struct IntervalEntry {
+    start: BigUint,
+    end: BigUint,
+    gen_id: usize,
+    /// Position in `TxGenerator::transactions`
+    position: usize,
+}
+
+impl IntervalIndex {
+    /// Indexes the transactions together with their position in the transactions of their generator.
+    /// The entries are sorted once, which is quick as transactions mostly come in order of their start time.
+    pub(crate) fn new<'a>(transactions: impl IntoIterator<Item = (&'a Transaction, usize)>) -> Self {
+        let mut entries: Vec<IntervalEntry> = transactions
+            .into_iter()
+            .map(|(tx, position)| IntervalEntry {
+                start: tx.event.start_time.clone(),
+                end: tx.event.end_time.clone(),
+                gen_id: tx.event.gen_id,
+                position,
+            })
+            .collect();
+        entries.sort_by(|a, b| a.start.cmp(&b.start));
+
+        let mut max_end: Vec<BigUint> = Vec::with_capacity(entries.len());
+        for entry in &entries {
+            let end = match max_end.last() {
+                Some(end) if *end > entry.end => end.clone(),
+                _ => entry.end.clone(),
+            };
+            max_end.push(end);
+        }
+        let mut by_end = entries.clone();
+        by_end.sort_by(|a, b| a.end.cmp(&b.end));
+        Self { entries, max_end, by_end }
+    }
+
+    /// Returns generator id and position of all transactions that overlap with the closed interval `[start, end]`,
+    /// ordered by their start time.
+    pub(crate) fn query<'a>(&'a self, start: &'a BigUint, end: &'a BigUint) -> impl Iterator<Item = (usize, usize)> + 'a {
+        let last = self.entries.partition_point(|e| e.start <= *end);
+        let first = self.max_end[..last].partition_point(|max_end| max_end < start);
+        self.entries[first..last]
+            .iter()
+            .filter(move |e| e.end >= *start)
+            .map(|e| (e.gen_id, e.position))
+    }
+
+    /// Returns generator id and position of all transactions that start after `time`, ordered by their start time.
+    pub(crate) fn starting_after(&self, time: &BigUint) -> impl Iterator<Item = (usize, usize)> + '_ {
+        let first = self.entries.partition_point(|e| e.start <= *time);
+        self.entries[first..].iter().map(|e| (e.gen_id, e.position))
+    }
+
+    /// Returns generator id and position of all transactions that end before `time`, latest end time first.
+    pub(crate) fn ending_before(&self, time: &BigUint) -> impl Iterator<Item = (usize, usize)> + '_ {
+        let last = self.by_end.partition_point(|e| e.end < *time);
+        self.by_end[..last].iter().rev().map(|e| (e.gen_id, e.position))
+    }
+}
@@ -0,0 +1,58 @@
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+
+use num_bigint::BigUint;
+
+use crate::types::Transaction;
+
+/// Rows in which overlapping transactions can be drawn without covering each other, see `FTR::lanes()`.
+///
+/// Transactions are placed in order of their start time, ties broken by id, each into the lowest lane that is free
+/// at its start. A lane is free again at the end time of its last transaction. As the assignment only depends on
+/// the transactions, it stays the same at every zoom level.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LaneAssignment {
+    lanes: HashMap<usize, usize>,
+    num_lanes: usize,
+}
+
+impl LaneAssignment {
+    pub fn new<'a>(transactions: impl IntoIterator<Item = &'a Transaction>) -> Self {
+        let mut transactions: Vec<&Transaction> = transactions.into_iter().collect();
+        transactions.sort_by(|a, b| (&a.event.start_time, a.event.tx_id).cmp(&(&b.event.start_time, b.event.tx_id)));
+
+        let mut assignment = LaneAssignment::default();
+        // end times of the lanes in use and the lanes that are free
+        let mut busy: BinaryHeap<Reverse<(&BigUint, usize)>> = BinaryHeap::new();
+        let mut free: BinaryHeap<Reverse<usize>> = BinaryHeap::new();
+        for tx in transactions {
+            while let Some(Reverse((end, lane))) = busy.peek() {
+                if **end > tx.event.start_time {
+                    break;
+                }
+                free.push(Reverse(*lane));
+                busy.pop();
+            }
+            let lane = match free.pop() {
+                Some(Reverse(lane)) => lane,
+                None => {
+                    assignment.num_lanes += 1;
+                    assignment.num_lanes - 1
+                }
+            };
+            busy.push(Reverse(((&tx.event.end_time).max(&tx.event.start_time), lane)));
+            assignment.lanes.insert(tx.event.tx_id, lane);
+        }
+        assignment
+    }
+
+    /// Returns the lane of the transaction with id `tx_id`, counted from 0.
+    pub fn lane(&self, tx_id: usize) -> Option<usize> {
+        self.lanes.get(&tx_id).copied()
+    }
+
+    /// Returns the number of lanes needed for all transactions.
+    pub fn num_lanes(&self) -> usize {
+        self.num_lanes
+    }
+}
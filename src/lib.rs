@@ -1,6 +1,24 @@
 pub mod parse;
 mod cbor_decoder;
+mod container;
 mod ftr_parser;
+mod interval_index;
 pub mod types;
+pub mod stream_reader;
+pub mod tail_reader;
+pub mod query;
+pub mod de;
+pub mod attribute_tree;
+pub mod scope;
+pub mod hierarchy;
+pub mod relation_graph;
+pub mod stats;
+pub mod concurrency;
+pub mod utilization;
+pub mod lod;
+pub mod lanes;
+pub mod lint;
+#[cfg(feature = "tokio")]
+pub mod async_parse;
 
 mod tests;
\ No newline at end of file
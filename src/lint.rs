@@ -0,0 +1,174 @@
+use std::collections::HashSet;
+use std::fmt;
+
+use num_bigint::BigInt;
+
+use crate::types::FTR;
+
+/// How bad a finding of `FTR::lint()` is, ordered from `Info` to `Error`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    /// Allowed, but worth knowing
+    Info,
+    /// Probably a mistake of the writer of the trace
+    Warning,
+    /// The trace is broken
+    Error,
+}
+
+/// What is wrong.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Check {
+    NegativeDuration,
+    ZeroDuration,
+    DuplicateTxId,
+    UnknownStream,
+    UnknownRelationTx,
+    BeyondMaxTimestamp,
+    TxBlockBounds,
+    UnusedDictionaryEntry,
+    /// Checks that were not run because the transactions or relations they need are not loaded
+    Skipped,
+}
+
+/// Where the problem is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Location {
+    /// The trace as a whole
+    Trace,
+    Generator(usize),
+    Transaction { tx_id: usize, gen_id: usize },
+    TxBlock { stream_id: usize, block_idx: usize },
+    /// Position of the relation in `FTR::relations()`
+    Relation(usize),
+    /// Id of the entry in `FTR::str_dict`
+    DictionaryEntry(usize),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Finding {
+    pub severity: Severity,
+    pub check: Check,
+    pub location: Location,
+    pub message: String,
+}
+
+impl Finding {
+    fn new(check: Check, location: Location, message: String) -> Self {
+        let severity = match check {
+            Check::NegativeDuration | Check::DuplicateTxId | Check::UnknownStream | Check::UnknownRelationTx | Check::BeyondMaxTimestamp => Severity::Error,
+            Check::ZeroDuration | Check::TxBlockBounds => Severity::Warning,
+            Check::UnusedDictionaryEntry | Check::Skipped => Severity::Info,
+        };
+        Self { severity, check, location, message }
+    }
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Location::Trace => write!(f, "trace"),
+            Location::Generator(gen_id) => write!(f, "generator {}", gen_id),
+            Location::Transaction { tx_id, gen_id } => write!(f, "transaction {} of generator {}", tx_id, gen_id),
+            Location::TxBlock { stream_id, block_idx } => write!(f, "transaction block {} of stream {}", block_idx, stream_id),
+            Location::Relation(idx) => write!(f, "relation {}", idx),
+            Location::DictionaryEntry(id) => write!(f, "dictionary entry {}", id),
+        }
+    }
+}
+
+/// Shows the finding as `error: transaction 5 of generator 4: ...`.
+impl fmt::Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let severity = match self.severity {
+            Severity::Info => "info",
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        };
+        write!(f, "{}: {}: {}", severity, self.location, self.message)
+    }
+}
+
+// checks everything that is in memory, see `FTR::lint()`
+pub(crate) fn lint(ftr: &FTR) -> Vec<Finding> {
+    let mut findings = vec![];
+    let mut gen_ids: Vec<&usize> = ftr.tx_generators.keys().collect();
+    gen_ids.sort();
+
+    for gen_id in &gen_ids {
+        let gen = &ftr.tx_generators[gen_id];
+        if !ftr.tx_streams.contains_key(&gen.stream_id) {
+            findings.push(Finding::new(Check::UnknownStream, Location::Generator(gen.id), format!("stream {} does not exist", gen.stream_id)));
+        }
+    }
+
+    let mut seen: HashSet<usize> = HashSet::new();
+    for gen_id in &gen_ids {
+        for tx in &ftr.tx_generators[gen_id].transactions {
+            let event = &tx.event;
+            let location = Location::Transaction { tx_id: event.tx_id, gen_id: event.gen_id };
+            if event.end_time < event.start_time {
+                findings.push(Finding::new(Check::NegativeDuration, location, format!("ends at {} before it starts at {}", event.end_time, event.start_time)));
+            } else if event.end_time == event.start_time {
+                findings.push(Finding::new(Check::ZeroDuration, location, format!("starts and ends at {}", event.start_time)));
+            }
+            if !seen.insert(event.tx_id) {
+                findings.push(Finding::new(Check::DuplicateTxId, location, format!("id {} is used by another transaction", event.tx_id)));
+            }
+            if BigInt::from(event.end_time.clone()) > ftr.max_timestamp {
+                findings.push(Finding::new(Check::BeyondMaxTimestamp, location, format!("ends at {} after the end of the trace at {}", event.end_time, ftr.max_timestamp)));
+            }
+        }
+    }
+
+    let mut stream_ids: Vec<&usize> = ftr.tx_streams.keys().collect();
+    stream_ids.sort();
+    for stream_id in &stream_ids {
+        for (block_idx, tx_block) in ftr.tx_streams[stream_id].tx_blocks.iter().enumerate() {
+            let Some((first_start, last_end)) = &tx_block.tx_times else { continue };
+            if BigInt::from(first_start.clone()) < BigInt::from(tx_block.start_time) || BigInt::from(last_end.clone()) > BigInt::from(tx_block.end_time) {
+                let location = Location::TxBlock { stream_id: **stream_id, block_idx };
+                let message = format!(
+                    "transactions from {} to {} are outside of the block from {} to {}",
+                    first_start, last_end, tx_block.start_time, tx_block.end_time
+                );
+                findings.push(Finding::new(Check::TxBlockBounds, location, message));
+            }
+        }
+    }
+
+    // the remaining checks need every transaction
+    let unloaded: Vec<String> = stream_ids.iter().filter(|id| !ftr.tx_streams[*id].transactions_loaded).map(|id| id.to_string()).collect();
+    if !unloaded.is_empty() {
+        let message = format!("streams {} are not loaded, so their transactions, the relations and the dictionary entries are not checked", unloaded.join(", "));
+        findings.push(Finding::new(Check::Skipped, Location::Trace, message));
+        return findings;
+    }
+    if !ftr.relations_loaded() {
+        let message = "not all relations are loaded, so the relations and the dictionary entries are not checked".to_string();
+        findings.push(Finding::new(Check::Skipped, Location::Trace, message));
+    }
+
+    if ftr.relations_loaded() {
+        for (idx, rel) in ftr.tx_relations.iter().enumerate() {
+            for (end, tx_id) in [("source", rel.source_tx_id), ("sink", rel.sink_tx_id)] {
+                if !ftr.tx_index.contains_key(&tx_id) {
+                    findings.push(Finding::new(Check::UnknownRelationTx, Location::Relation(idx), format!("{} transaction {} does not exist", end, tx_id)));
+                }
+            }
+        }
+    }
+
+    // writers put the empty string into the dictionary whether they need it or not
+    if ftr.relations_loaded() {
+        let mut entries: Vec<(&usize, &String)> = ftr.str_dict
+            .iter()
+            .filter(|(id, s)| !s.is_empty() && !ftr.used_dict_ids.contains(id))
+            .collect();
+        entries.sort();
+        for (id, s) in entries {
+            findings.push(Finding::new(Check::UnusedDictionaryEntry, Location::DictionaryEntry(*id), format!("\"{}\" is not used", s)));
+        }
+    }
+    findings
+}
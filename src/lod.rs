@@ -0,0 +1,182 @@
+use num_bigint::BigUint;
+
+use crate::concurrency::ConcurrencyProfile;
+use crate::types::Transaction;
+
+// upper limit for the number of leaves, which bounds the memory of a summary
+const MAX_LEAVES: usize = 1 << 16;
+
+/// A summary of the transactions of a stream at many resolutions, so that a zoomed-out view does not need
+/// to look at every transaction, see `FTR::lod_summary()`.
+///
+/// The time from 0 to the end of the last transaction is divided into leaves of equal width, which are combined
+/// pairwise into coarser levels. Any range of time is summarized in logarithmic time from these levels.
+#[derive(Debug, Clone)]
+pub struct LodSummary {
+    width: BigUint,
+    leaves: usize,
+    // binary tree in an array, the children of node i are 2i and 2i + 1 and the leaves start at `leaves`
+    nodes: Vec<LodNode>,
+}
+
+#[derive(Debug, Clone, Default)]
+struct LodNode {
+    count: usize,
+    coverage: u64,
+    min_duration: Option<u64>,
+    max_duration: Option<u64>,
+    // number of transactions by generator id, ordered by the id
+    generators: Vec<(usize, usize)>,
+}
+
+/// Summary of the transactions from `start` up to, but not including, `end`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LodBucket {
+    pub start: BigUint,
+    pub end: BigUint,
+    /// Number of transactions that start in the bucket
+    pub count: usize,
+    /// Time in the bucket in which at least one transaction is in flight
+    pub coverage: u64,
+    /// Shortest duration of the transactions that start in the bucket
+    pub min_duration: Option<u64>,
+    pub max_duration: Option<u64>,
+    /// Id of the generator with the most transactions in the bucket
+    pub dominant_generator: Option<usize>,
+}
+
+impl LodBucket {
+    fn empty(start: BigUint, end: BigUint) -> Self {
+        LodBucket { start, end, count: 0, coverage: 0, min_duration: None, max_duration: None, dominant_generator: None }
+    }
+}
+
+impl LodNode {
+    fn merge(&self, other: &LodNode) -> LodNode {
+        let mut generators = self.generators.clone();
+        for (gen_id, count) in &other.generators {
+            match generators.binary_search_by_key(gen_id, |(id, _)| *id) {
+                Ok(position) => generators[position].1 += count,
+                Err(position) => generators.insert(position, (*gen_id, *count)),
+            }
+        }
+        LodNode {
+            count: self.count + other.count,
+            coverage: self.coverage.saturating_add(other.coverage),
+            min_duration: self.min_duration.into_iter().chain(other.min_duration).min(),
+            max_duration: self.max_duration.into_iter().chain(other.max_duration).max(),
+            generators,
+        }
+    }
+
+    fn add(&mut self, gen_id: usize, duration: u64) {
+        self.count += 1;
+        self.min_duration = Some(self.min_duration.map_or(duration, |min| min.min(duration)));
+        self.max_duration = Some(self.max_duration.map_or(duration, |max| max.max(duration)));
+        match self.generators.binary_search_by_key(&gen_id, |(id, _)| *id) {
+            Ok(position) => self.generators[position].1 += 1,
+            Err(position) => self.generators.insert(position, (gen_id, 1)),
+        }
+    }
+}
+
+impl LodSummary {
+    pub fn new<'a>(transactions: impl IntoIterator<Item = &'a Transaction>) -> Self {
+        let transactions: Vec<&Transaction> = transactions.into_iter().collect();
+        let span = transactions.iter().map(|tx| (&tx.event.end_time).max(&tx.event.start_time)).max().cloned().unwrap_or_default() + 1u8;
+        let leaves = transactions.len().next_power_of_two().min(MAX_LEAVES);
+        let width: BigUint = ((&span + leaves - 1u8) / leaves).max(BigUint::from(1u8));
+
+        let mut summary = LodSummary { width, leaves, nodes: vec![LodNode::default(); 2 * leaves] };
+        for tx in &transactions {
+            let duration = if tx.event.end_time > tx.event.start_time { &tx.event.end_time - &tx.event.start_time } else { BigUint::default() };
+            let leaf = summary.leaf(&tx.event.start_time);
+            summary.nodes[leaves + leaf].add(tx.event.gen_id, u64::try_from(duration).unwrap_or(u64::MAX));
+        }
+        for (start, end) in ConcurrencyProfile::new(transactions).intervals_above(0) {
+            for leaf in summary.leaf(&start)..=summary.leaf(&(&end - 1u8)) {
+                let from = (&start).max(&(&summary.width * leaf)).clone();
+                let to = (&end).min(&(&summary.width * (leaf + 1))).clone();
+                if from < to {
+                    let node = &mut summary.nodes[leaves + leaf];
+                    node.coverage = node.coverage.saturating_add(u64::try_from(to - from).unwrap_or(u64::MAX));
+                }
+            }
+        }
+        for idx in (1..leaves).rev() {
+            summary.nodes[idx] = summary.nodes[2 * idx].merge(&summary.nodes[2 * idx + 1]);
+        }
+        summary
+    }
+
+    /// Returns the number of transactions in the summary.
+    pub fn len(&self) -> usize {
+        self.nodes[1].count
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the width of the finest buckets. Buckets that are requested narrower than that are widened to it.
+    pub fn resolution(&self) -> &BigUint {
+        &self.width
+    }
+
+    /// Divides the time from `start` to `end` into `buckets` buckets of equal width, e.g. one per pixel,
+    /// and summarizes each of them. The bounds of the buckets are moved to the closest finest buckets,
+    /// so that neighbouring buckets can cover the same time if they are narrower than `resolution()`.
+    /// Buckets after the end of the last transaction are empty.
+    pub fn query(&self, start: impl Into<BigUint>, end: impl Into<BigUint>, buckets: usize) -> Vec<LodBucket> {
+        let (start, end) = (start.into(), end.into());
+        if end <= start || buckets == 0 {
+            return vec![];
+        }
+        let span = &end - &start;
+        (0..buckets)
+            .map(|idx| {
+                let bucket_start = &start + &span * idx / buckets;
+                let bucket_end = &start + &span * (idx + 1) / buckets;
+                if bucket_start >= &self.width * self.leaves {
+                    return LodBucket::empty(bucket_start, bucket_end);
+                }
+                let from = self.leaf(&bucket_start);
+                let to = if bucket_end > bucket_start { self.leaf(&(bucket_end - 1u8)) + 1 } else { from + 1 };
+                self.summarize(from, to)
+            })
+            .collect()
+    }
+
+    // the leaf that contains `time`, or the last leaf
+    fn leaf(&self, time: &BigUint) -> usize {
+        usize::try_from(time / &self.width).unwrap_or(usize::MAX).min(self.leaves - 1)
+    }
+
+    // combines the leaves from `from` up to, but not including, `to`
+    fn summarize(&self, from: usize, to: usize) -> LodBucket {
+        let mut node = LodNode::default();
+        let (mut left, mut right) = (from + self.leaves, to + self.leaves);
+        while left < right {
+            if left % 2 == 1 {
+                node = node.merge(&self.nodes[left]);
+                left += 1;
+            }
+            if right % 2 == 1 {
+                right -= 1;
+                node = node.merge(&self.nodes[right]);
+            }
+            left /= 2;
+            right /= 2;
+        }
+        LodBucket {
+            start: &self.width * from,
+            end: &self.width * to,
+            count: node.count,
+            coverage: node.coverage,
+            min_duration: node.min_duration,
+            max_duration: node.max_duration,
+            // the lowest id wins a tie
+            dominant_generator: node.generators.iter().rev().max_by_key(|(_, count)| *count).map(|(gen_id, _)| *gen_id),
+        }
+    }
+}
@@ -1,56 +1,94 @@
-use std::collections::HashMap;
 use std::fs::File;
-use std::io::{Cursor, SeekFrom};
+use std::io::{Chain, Cursor, Read, SeekFrom};
 use std::path::PathBuf;
-use num_bigint::BigInt;
 
-use crate::cbor_decoder::CborDecoder;
-use crate::ftr_parser;
+use crate::cbor_decoder::{CborDecoder, SequentialReader};
+#[cfg(any(feature = "gzip", feature = "zstd", feature = "xz"))]
+use crate::container;
+use crate::container::Container;
 use crate::ftr_parser::FtrParser;
-use crate::types::{FTR, Timescale};
+use crate::stream_reader::FtrStreamReader;
+use crate::tail_reader::FtrTailReader;
+use crate::types::FTR;
 
 /// The function you probably want to call first.
-/// Parses the file with the given name and returns a FTR variable with all streams, generators and relations already accessible.
-/// However, it does not yet load the transactions themselves into memory. This can be done with 'load_stream_into_memory()'.
+/// Parses the file with the given name and returns a FTR variable with all streams and generators already accessible.
+/// However, it does not yet load the transactions themselves into memory. This can be done with 'load_stream_into_memory()',
+/// which also loads the relations of these transactions. All relations can be loaded with 'load_relations()'.
+///
+/// Files compressed with gzip, zstd or xz are detected and decompressed into a temporary file, if the corresponding
+/// cargo feature (`gzip`, `zstd` or `xz`) is enabled.
 pub fn parse_ftr(file_name: PathBuf) -> color_eyre::Result<FTR>{
 
-    let mut ftr = FTR{
-        time_scale: Timescale::None,
-        str_dict: HashMap::new(),
-        tx_streams: HashMap::new(),
-        max_timestamp: BigInt::from(0),
-        tx_generators: HashMap::new(),
-        tx_relations: vec![],
-        path: Some(file_name.clone()),
-    };
-    let mut ftr_parser = FtrParser::new(&mut ftr);
+    let mut reader = File::open(&file_name)?;
+
+    let container = Container::detect(&mut reader)?;
+    if container != Container::Plain {
+        return parse_compressed(container, reader);
+    }
 
-    let reader = File::open(file_name)?;
+    let mut ftr = FTR::new(Some(file_name));
+    let mut ftr_parser = FtrParser::new(&mut ftr);
 
     ftr_parser.load(reader)?;
 
     Ok(ftr)
 }
 
+#[cfg(any(feature = "gzip", feature = "zstd", feature = "xz"))]
+fn parse_compressed(container: Container, file: File) -> color_eyre::Result<FTR>{
+    let temp_file = container::decompress_to_temp(container, file)?;
+
+    let mut ftr = FTR::new(Some(temp_file.to_path_buf()));
+    let mut ftr_parser = FtrParser::new(&mut ftr);
+
+    ftr_parser.load(File::open(&temp_file)?)?;
+
+    ftr.temp_file = Some(temp_file);
+    Ok(ftr)
+}
+
+#[cfg(not(any(feature = "gzip", feature = "zstd", feature = "xz")))]
+fn parse_compressed(container: Container, _file: File) -> color_eyre::Result<FTR>{
+    Err(container.missing_feature())
+}
+
 pub fn read_from_bytes(bytes: Vec<u8>) -> color_eyre::Result<FTR>{
 
-    let mut ftr = FTR{
-        time_scale: Timescale::None,
-        str_dict: HashMap::new(),
-        tx_streams: HashMap::new(),
-        max_timestamp: BigInt::from(0),
-        tx_generators: HashMap::new(),
-        tx_relations: vec![],
-        path: None,
-    };
+    let mut ftr = FTR::new(None);
     let mut ftr_parser = FtrParser::new(&mut ftr);
 
     ftr_parser.load(Cursor::new(bytes))?;
-    ftr_parser::connect_relations_and_transactions(&mut ftr);
 
     Ok(ftr)
 }
 
+/// Parses a FTR from a reader that does not need to be seekable, e.g. a pipe, stdin or a socket.
+/// As the input cannot be read a second time, all transactions are loaded into memory right away.
+pub fn read_from_reader<R: Read>(reader: R) -> color_eyre::Result<FTR>{
+
+    let mut ftr = FTR::new(None);
+    let mut ftr_parser = FtrParser::new(&mut ftr);
+
+    ftr_parser.load(SequentialReader(reader))?;
+
+    Ok(ftr)
+}
+
+/// Like `read_from_reader()`, but the transactions are not kept in memory.
+/// Instead, the returned reader is an iterator that hands out each transaction as soon as its block has been read.
+pub fn stream_from_reader<R: Read>(reader: R) -> color_eyre::Result<FtrStreamReader<R>>{
+    FtrStreamReader::new(reader)
+}
+
+/// Opens a FTR file that is still being written, e.g. while the simulation is running.
+/// Everything written so far is parsed right away, newly appended data can be parsed with `FtrTailReader::refresh()`.
+pub fn tail_ftr(file_name: PathBuf) -> color_eyre::Result<FtrTailReader>{
+    let mut tail_reader = FtrTailReader::new(file_name);
+    tail_reader.refresh()?;
+    Ok(tail_reader)
+}
+
 pub fn is_ftr<R: std::io::Read + std::io::Seek>(input: &mut R) -> bool {
     let mut cbor_decoder = CborDecoder::new(input);
     let tag = cbor_decoder.read_tag();
@@ -61,3 +99,12 @@ pub fn is_ftr<R: std::io::Read + std::io::Seek>(input: &mut R) -> bool {
     }
 }
 
+/// Same as `is_ftr()` for inputs that cannot seek. Reads until the three bytes of the FTR tag are there or the input ends,
+/// and returns them in front of the rest of the input, so that it can still be parsed from the start.
+pub fn is_ftr_buffered<R: Read>(mut input: R) -> (bool, Chain<Cursor<Vec<u8>>, R>) {
+    let mut head = Vec::with_capacity(3);
+    // a pipe may hand out less than asked for, 'read_to_end()' keeps reading until it has got all or the input ends
+    let is_ftr = input.by_ref().take(3).read_to_end(&mut head).is_ok()
+        && matches!(CborDecoder::new(&head[..]).read_tag(), Ok(55799));
+    (is_ftr, Cursor::new(head).chain(input))
+}
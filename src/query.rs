@@ -0,0 +1,590 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::str::FromStr;
+
+use color_eyre::eyre::{bail, eyre};
+use num_bigint::BigUint;
+use regex::Regex;
+
+use crate::types::{Attribute, AttributeType, DataType, Timescale, Transaction, FTR};
+
+/// A query to filter transactions by their attributes, streams, generators and timing.
+///
+/// A query combines conditions with `&&`, `||`, `!` and parentheses, e.g.
+/// `stream =~ "tr.*" && addr >= 0x1000 && duration > 10ns && kind == BEGIN`.
+///
+/// A condition compares a field with a value using `==`, `!=`, `<`, `<=`, `>`, `>=` or `=~`, which matches a regular
+/// expression. The fields are
+/// - `stream` and `generator`: the names of the stream and generator of the transaction,
+/// - `id`, `start`, `end` and `duration`: the id and the timing of the transaction,
+/// - `kind`: the kind of an attribute, i.e. `BEGIN`, `RECORD` or `END`,
+/// - any other name, or a quoted name like `"start"`: the attribute with this name.
+///
+/// `kind` only allows `==` and `!=`. Combined by `&&` with conditions on attributes, it restricts them to the
+/// attributes of this kind: `kind == BEGIN && addr > 5` matches transactions with a BEGIN attribute `addr` greater
+/// than 5, and `kind != BEGIN && addr > 5` those with such an attribute that is not BEGIN. Otherwise `kind == BEGIN`
+/// matches transactions that have a BEGIN attribute and `kind != BEGIN` those that have none.
+///
+/// Values are integers (`42`, `-1`, `0x1000`, `0b1010`), floating point numbers, times with a unit (`10ns`, `1.5us`),
+/// quoted strings, `true` and `false`. Other words, like `BEGIN` or the value of an enumeration, are taken as strings.
+/// Times are converted to the time scale of the trace, while plain numbers are taken as they are.
+///
+/// Conditions on attributes are false if the transaction does not have the attribute or if the value does not fit
+/// its data type. If a transaction has several attributes with the same name, one of them has to match.
+///
+/// Parentheses and `!` can be nested up to `MAX_NESTING` levels.
+#[derive(Debug, Clone)]
+pub struct Query {
+    source: String,
+    expr: Expr,
+}
+
+/// How deep parentheses and `!` can be nested in a `Query`.
+pub const MAX_NESTING: usize = 256;
+
+impl Query {
+    pub fn parse(query: &str) -> color_eyre::Result<Self> {
+        let tokens = tokenize(query)?;
+        let mut parser = Parser { tokens, pos: 0, depth: 0 };
+        let expr = parser.parse_or()?;
+        if let Some((token, position)) = parser.tokens.get(parser.pos) {
+            bail!("Unexpected {} at position {} of the query", token, position);
+        }
+        Ok(Self { source: query.to_string(), expr })
+    }
+
+    /// Returns true if the transaction matches the query. `ftr` has to be the FTR the transaction was loaded from.
+    pub fn matches(&self, ftr: &FTR, tx: &Transaction) -> bool {
+        self.expr.eval(ftr, tx)
+    }
+
+    /// Returns all loaded transactions of the FTR that match the query, ordered by stream and generator.
+    pub fn filter<'a>(&'a self, ftr: &'a FTR) -> impl Iterator<Item = &'a Transaction> + 'a {
+        let mut gen_ids: Vec<(usize, usize)> = ftr.tx_generators.values().map(|gen| (gen.stream_id, gen.id)).collect();
+        gen_ids.sort();
+        gen_ids
+            .into_iter()
+            .flat_map(move |(_, gen_id)| &ftr.tx_generators[&gen_id].transactions)
+            .filter(move |tx| self.matches(ftr, tx))
+    }
+}
+
+impl FromStr for Query {
+    type Err = color_eyre::Report;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Query::parse(s)
+    }
+}
+
+impl fmt::Display for Query {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.source)
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Expr {
+    And(Vec<Expr>),
+    Or(Vec<Expr>),
+    Not(Box<Expr>),
+    Condition(Condition),
+}
+
+impl Expr {
+    fn eval(&self, ftr: &FTR, tx: &Transaction) -> bool {
+        match self {
+            Expr::And(operands) => operands.iter().all(|expr| expr.eval(ftr, tx)),
+            Expr::Or(operands) => operands.iter().any(|expr| expr.eval(ftr, tx)),
+            Expr::Not(a) => !a.eval(ftr, tx),
+            Expr::Condition(condition) => condition.eval(ftr, tx),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Field {
+    Stream,
+    Generator,
+    Id,
+    Start,
+    End,
+    Duration,
+    Kind,
+    Attribute(String),
+}
+
+impl Field {
+    fn from_name(name: &str) -> Self {
+        match name {
+            "stream" => Field::Stream,
+            "generator" => Field::Generator,
+            "id" => Field::Id,
+            "start" => Field::Start,
+            "end" => Field::End,
+            "duration" => Field::Duration,
+            "kind" => Field::Kind,
+            _ => Field::Attribute(name.to_string()),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Op {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Matches(Regex),
+}
+
+impl Op {
+    fn holds(&self, ordering: Option<Ordering>) -> bool {
+        match (self, ordering) {
+            (_, None) | (Op::Matches(_), _) => false,
+            (Op::Eq, Some(o)) => o.is_eq(),
+            (Op::Ne, Some(o)) => o.is_ne(),
+            (Op::Lt, Some(o)) => o.is_lt(),
+            (Op::Le, Some(o)) => o.is_le(),
+            (Op::Gt, Some(o)) => o.is_gt(),
+            (Op::Ge, Some(o)) => o.is_ge(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Value {
+    Int(i128),
+    Float(f64),
+    /// A time with the decimal exponent of its unit, e.g. -9 for ns
+    Time(f64, i32),
+    Str(String),
+    Bool(bool),
+}
+
+impl Value {
+    fn is_number(&self) -> bool {
+        matches!(self, Value::Int(_) | Value::Float(_) | Value::Time(..))
+    }
+
+    // numbers are compared as integers if possible, times are converted to the time scale of the trace
+    fn cmp_number(&self, number: Number, ftr: &FTR) -> Option<Ordering> {
+        match (number, self) {
+            (Number::Int(a), Value::Int(b)) => Some(a.cmp(b)),
+            (Number::Int(a), Value::Float(b)) => (a as f64).partial_cmp(b),
+            (Number::Float(a), Value::Int(b)) => a.partial_cmp(&(*b as f64)),
+            (Number::Float(a), Value::Float(b)) => a.partial_cmp(b),
+            (Number::Int(a), Value::Time(value, exponent)) => (a as f64).partial_cmp(&in_time_scale(*value, *exponent, ftr.time_scale)),
+            (Number::Float(a), Value::Time(value, exponent)) => a.partial_cmp(&in_time_scale(*value, *exponent, ftr.time_scale)),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Number {
+    Int(i128),
+    Float(f64),
+}
+
+impl From<&BigUint> for Number {
+    fn from(value: &BigUint) -> Self {
+        match i128::try_from(value) {
+            Ok(value) => Number::Int(value),
+            Err(_) => Number::Float(f64::INFINITY),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Condition {
+    field: Field,
+    op: Op,
+    value: Value,
+    // kinds that attributes must (true) or must not (false) have to be compared, see `scope_kinds()`
+    kinds: Vec<(AttributeType, bool)>,
+}
+
+impl Condition {
+    // checks that the value can be compared with the field, as far as this is known before looking at a transaction
+    fn new(field: Field, op: Op, value: Value) -> color_eyre::Result<Self> {
+        let is_regex = matches!(op, Op::Matches(_));
+        let valid = match &field {
+            Field::Stream | Field::Generator => matches!(value, Value::Str(_)),
+            Field::Id => !is_regex && matches!(value, Value::Int(_) | Value::Float(_)),
+            Field::Start | Field::End | Field::Duration => !is_regex && value.is_number(),
+            Field::Kind => matches!(op, Op::Eq | Op::Ne) && matches!(&value, Value::Str(s) if parse_kind(s).is_some()),
+            Field::Attribute(_) => !is_regex || matches!(value, Value::Str(_)),
+        };
+        if !valid {
+            bail!("Cannot compare {:?} with {:?}", field, value);
+        }
+        Ok(Self { field, op, value, kinds: vec![] })
+    }
+
+    fn eval(&self, ftr: &FTR, tx: &Transaction) -> bool {
+        match &self.field {
+            Field::Stream => {
+                let stream = ftr.get_generator(tx.event.gen_id).and_then(|gen| ftr.get_stream(gen.stream_id));
+                stream.is_some_and(|stream| self.eval_str(&stream.name))
+            }
+            Field::Generator => ftr.get_generator(tx.event.gen_id).is_some_and(|gen| self.eval_str(&gen.name)),
+            Field::Id => self.op.holds(self.value.cmp_number(Number::Int(tx.event.tx_id as i128), ftr)),
+            Field::Start => self.op.holds(self.value.cmp_number((&tx.event.start_time).into(), ftr)),
+            Field::End => self.op.holds(self.value.cmp_number((&tx.event.end_time).into(), ftr)),
+            Field::Duration => {
+                let duration = match (Number::from(&tx.event.end_time), Number::from(&tx.event.start_time)) {
+                    (Number::Int(end), Number::Int(start)) => Number::Int(end - start),
+                    _ => Number::Float(f64::INFINITY),
+                };
+                self.op.holds(self.value.cmp_number(duration, ftr))
+            }
+            Field::Kind => {
+                let Value::Str(kind) = &self.value else { return false };
+                let kind = parse_kind(kind);
+                let found = tx.attributes.iter().any(|attr| Some(&attr.kind) == kind.as_ref());
+                found == matches!(self.op, Op::Eq)
+            }
+            Field::Attribute(name) => tx.attributes
+                .iter()
+                .filter(|attr| attr.name == *name && self.kinds.iter().all(|(kind, equal)| (attr.kind == *kind) == *equal))
+                .any(|attr| self.eval_attribute(attr, ftr)),
+        }
+    }
+
+    fn eval_str(&self, s: &str) -> bool {
+        match (&self.op, &self.value) {
+            (Op::Matches(regex), _) => regex.is_match(s),
+            (op, Value::Str(value)) => op.holds(Some(s.cmp(value))),
+            _ => false,
+        }
+    }
+
+    fn eval_attribute(&self, attr: &Attribute, ftr: &FTR) -> bool {
+        if let Op::Matches(regex) = &self.op {
+            return !matches!(attr.data_type, DataType::Error) && regex.is_match(&attr.value());
+        }
+        let number = match &attr.data_type {
+            DataType::Boolean(b) => {
+                return matches!(self.value, Value::Bool(_)) && self.op.holds(Some(b.cmp(&(self.value == Value::Bool(true)))));
+            }
+            DataType::Enumeration(s) | DataType::String(s) => return self.eval_str(s),
+            DataType::BitVector(s) | DataType::LogicVector(s) => {
+                if matches!(self.value, Value::Str(_)) {
+                    return self.eval_str(s);
+                }
+                // vectors without x or z bits can be compared with numbers
+                match i128::from_str_radix(s, 2) {
+                    Ok(value) => Number::Int(value),
+                    Err(_) => return false,
+                }
+            }
+            DataType::Integer(i) => Number::Int(*i as i128),
+            DataType::Unsigned(u) | DataType::Pointer(u) => Number::Int(*u as i128),
+            DataType::Time(t) => Number::Int(*t as i128),
+            DataType::FloatingPointNumber(f) | DataType::FixedPointInteger(f) | DataType::UnsignedFixedPointInteger(f) => Number::Float(*f as f64),
+            DataType::Error => return false,
+        };
+        self.op.holds(self.value.cmp_number(number, ftr))
+    }
+}
+
+fn parse_kind(kind: &str) -> Option<AttributeType> {
+    match kind.to_ascii_uppercase().as_str() {
+        "BEGIN" => Some(AttributeType::BEGIN),
+        "RECORD" => Some(AttributeType::RECORD),
+        "END" => Some(AttributeType::END),
+        _ => None,
+    }
+}
+
+fn unit_exponent(unit: &str) -> Option<i32> {
+    match unit {
+        "s" => Some(0),
+        "ms" => Some(-3),
+        "us" => Some(-6),
+        "ns" => Some(-9),
+        "ps" => Some(-12),
+        "fs" => Some(-15),
+        _ => None,
+    }
+}
+
+// converts a time to the time scale of the trace. Traces without a time scale take the time as it is
+fn in_time_scale(value: f64, exponent: i32, time_scale: Timescale) -> f64 {
+    let scale_exponent = match time_scale {
+        Timescale::S => 0,
+        Timescale::Ms => -3,
+        Timescale::Us => -6,
+        Timescale::Ns => -9,
+        Timescale::Ps => -12,
+        Timescale::Fs => -15,
+        Timescale::Unit | Timescale::None => return value,
+    };
+    value * 10f64.powi(exponent - scale_exponent)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    Str(String),
+    Value(Value),
+    Op(&'static str),
+    And,
+    Or,
+    Not,
+    LParen,
+    RParen,
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Token::Ident(name) => write!(f, "'{}'", name),
+            Token::Str(s) => write!(f, "\"{}\"", s),
+            Token::Value(value) => write!(f, "{:?}", value),
+            Token::Op(op) => write!(f, "'{}'", op),
+            Token::And => write!(f, "'&&'"),
+            Token::Or => write!(f, "'||'"),
+            Token::Not => write!(f, "'!'"),
+            Token::LParen => write!(f, "'('"),
+            Token::RParen => write!(f, "')'"),
+        }
+    }
+}
+
+// splits the query into tokens together with their position
+fn tokenize(query: &str) -> color_eyre::Result<Vec<(Token, usize)>> {
+    let chars: Vec<char> = query.chars().collect();
+    let mut tokens = vec![];
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let next = chars.get(i + 1).copied();
+        let start = i;
+        let token = match c {
+            c if c.is_whitespace() => {
+                i += 1;
+                continue;
+            }
+            '(' => Token::LParen,
+            ')' => Token::RParen,
+            '&' if next == Some('&') => Token::And,
+            '|' if next == Some('|') => Token::Or,
+            '=' if next == Some('=') => Token::Op("=="),
+            '=' if next == Some('~') => Token::Op("=~"),
+            '!' if next == Some('=') => Token::Op("!="),
+            '<' if next == Some('=') => Token::Op("<="),
+            '>' if next == Some('=') => Token::Op(">="),
+            '!' => Token::Not,
+            '<' => Token::Op("<"),
+            '>' => Token::Op(">"),
+            '"' => {
+                let mut s = String::new();
+                i += 1;
+                loop {
+                    match chars.get(i) {
+                        None => bail!("Unterminated string starting at position {} of the query", start),
+                        Some('"') => break,
+                        Some('\\') if i + 1 < chars.len() => {
+                            s.push(chars[i + 1]);
+                            i += 2;
+                        }
+                        Some(c) => {
+                            s.push(*c);
+                            i += 1;
+                        }
+                    }
+                }
+                i += 1;
+                tokens.push((Token::Str(s), start));
+                continue;
+            }
+            c if c.is_ascii_digit() || (c == '-' && next.is_some_and(|n| n.is_ascii_digit())) => {
+                i += 1;
+                while i < chars.len() && (chars[i].is_ascii_alphanumeric() || chars[i] == '.' || chars[i] == '_') {
+                    i += 1;
+                }
+                let literal: String = chars[start..i].iter().collect();
+                tokens.push((Token::Value(parse_number(&literal).ok_or_else(|| eyre!("Invalid number '{}' at position {} of the query", literal, start))?), start));
+                continue;
+            }
+            c if c.is_alphabetic() || c == '_' => {
+                while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_' || chars[i] == '.') {
+                    i += 1;
+                }
+                tokens.push((Token::Ident(chars[start..i].iter().collect()), start));
+                continue;
+            }
+            _ => bail!("Unexpected character '{}' at position {} of the query", c, start),
+        };
+        i += match token {
+            Token::LParen | Token::RParen | Token::Not | Token::Op("<") | Token::Op(">") => 1,
+            _ => 2,
+        };
+        tokens.push((token, start));
+    }
+    Ok(tokens)
+}
+
+// parses integers, hexadecimal and binary numbers, floating point numbers and times with a unit
+fn parse_number(literal: &str) -> Option<Value> {
+    let (negative, digits) = match literal.strip_prefix('-') {
+        Some(digits) => (true, digits),
+        None => (false, literal),
+    };
+    let digits = digits.replace('_', "");
+    let sign = if negative {-1} else {1};
+
+    if let Some(hex) = digits.strip_prefix("0x") {
+        return i128::from_str_radix(hex, 16).ok().map(|v| Value::Int(sign * v));
+    }
+    if let Some(bin) = digits.strip_prefix("0b") {
+        return i128::from_str_radix(bin, 2).ok().map(|v| Value::Int(sign * v));
+    }
+    let unit_start = digits.find(|c: char| c.is_ascii_alphabetic()).unwrap_or(digits.len());
+    let (number, unit) = digits.split_at(unit_start);
+    if !unit.is_empty() {
+        let value: f64 = number.parse().ok()?;
+        return Some(Value::Time(sign as f64 * value, unit_exponent(unit)?));
+    }
+    if let Ok(value) = number.parse::<i128>() {
+        return Some(Value::Int(sign * value));
+    }
+    number.parse::<f64>().ok().map(|v| Value::Float(sign as f64 * v))
+}
+
+struct Parser {
+    tokens: Vec<(Token, usize)>,
+    pos: usize,
+    // parentheses and `!` around the current position
+    depth: usize,
+}
+
+impl Parser {
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).map(|(token, _)| token.clone());
+        self.pos += 1;
+        token
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|(token, _)| token)
+    }
+
+    fn expected(&self, what: &str) -> color_eyre::Report {
+        match self.tokens.get(self.pos) {
+            Some((token, position)) => eyre!("Expected {} at position {} of the query, found {}", what, position, token),
+            None => eyre!("Expected {} at the end of the query", what),
+        }
+    }
+
+    fn parse_or(&mut self) -> color_eyre::Result<Expr> {
+        let mut operands = vec![self.parse_and()?];
+        while self.peek() == Some(&Token::Or) {
+            self.pos += 1;
+            operands.push(self.parse_and()?);
+        }
+        Ok(if operands.len() == 1 { operands.pop().unwrap() } else { Expr::Or(operands) })
+    }
+
+    fn parse_and(&mut self) -> color_eyre::Result<Expr> {
+        let mut operands = vec![self.parse_unary()?];
+        while self.peek() == Some(&Token::And) {
+            self.pos += 1;
+            operands.push(self.parse_unary()?);
+        }
+        scope_kinds(&mut operands);
+        Ok(if operands.len() == 1 { operands.pop().unwrap() } else { Expr::And(operands) })
+    }
+
+    fn parse_unary(&mut self) -> color_eyre::Result<Expr> {
+        if !matches!(self.peek(), Some(Token::Not | Token::LParen)) {
+            return self.parse_condition();
+        }
+        if self.depth == MAX_NESTING {
+            bail!("More than {} nested parentheses or '!' at position {} of the query", MAX_NESTING, self.tokens[self.pos].1);
+        }
+        self.depth += 1;
+        let expr = if self.next() == Some(Token::Not) {
+            Expr::Not(Box::new(self.parse_unary()?))
+        } else {
+            let expr = self.parse_or()?;
+            if self.peek() != Some(&Token::RParen) {
+                return Err(self.expected("')'"));
+            }
+            self.pos += 1;
+            expr
+        };
+        self.depth -= 1;
+        Ok(expr)
+    }
+
+    fn parse_condition(&mut self) -> color_eyre::Result<Expr> {
+        let field = match self.peek() {
+            Some(Token::Ident(name)) => Field::from_name(name),
+            Some(Token::Str(name)) => Field::Attribute(name.clone()),
+            _ => return Err(self.expected("a field name")),
+        };
+        self.pos += 1;
+
+        let op = match self.peek() {
+            Some(Token::Op(op)) => *op,
+            _ => return Err(self.expected("a comparison")),
+        };
+        self.pos += 1;
+
+        let position = self.tokens.get(self.pos).map(|(_, position)| *position);
+        let value = match self.peek() {
+            Some(Token::Value(value)) => value.clone(),
+            Some(Token::Str(s)) => Value::Str(s.clone()),
+            Some(Token::Ident(word)) => match word.as_str() {
+                "true" => Value::Bool(true),
+                "false" => Value::Bool(false),
+                _ => Value::Str(word.clone()),
+            },
+            _ => return Err(self.expected("a value")),
+        };
+        self.next();
+
+        let op = match op {
+            "==" => Op::Eq,
+            "!=" => Op::Ne,
+            "<" => Op::Lt,
+            "<=" => Op::Le,
+            ">" => Op::Gt,
+            ">=" => Op::Ge,
+            _ => match &value {
+                Value::Str(pattern) => Op::Matches(Regex::new(pattern).map_err(|err| {
+                    eyre!("Invalid regular expression at position {} of the query: {}", position.unwrap_or_default(), err)
+                })?),
+                _ => bail!("'=~' needs a regular expression at position {} of the query", position.unwrap_or_default()),
+            },
+        };
+        Ok(Expr::Condition(Condition::new(field, op, value)?))
+    }
+}
+
+// conditions on `kind` that are combined with conditions on attributes restrict these to attributes of the kind,
+// instead of being checked on their own
+fn scope_kinds(operands: &mut Vec<Expr>) {
+    let is_attribute = |expr: &Expr| matches!(expr, Expr::Condition(Condition { field: Field::Attribute(_), .. }));
+    if !operands.iter().any(is_attribute) {
+        return;
+    }
+    let mut kinds = vec![];
+    operands.retain(|expr| match expr {
+        Expr::Condition(Condition { field: Field::Kind, op, value: Value::Str(kind), .. }) => {
+            kinds.extend(parse_kind(kind).map(|kind| (kind, matches!(op, Op::Eq))));
+            false
+        }
+        _ => true,
+    });
+    for expr in operands {
+        if let Expr::Condition(condition @ Condition { field: Field::Attribute(_), .. }) = expr {
+            condition.kinds.extend(kinds.iter().cloned());
+        }
+    }
+}
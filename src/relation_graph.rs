@@ -0,0 +1,254 @@
+use std::collections::{HashMap, HashSet};
+
+use num_bigint::{BigInt, BigUint};
+
+use crate::types::FTR;
+
+/// The relations between loaded transactions as a directed graph, to follow chains of related transactions
+/// like request, interconnect, memory and response, see `FTR::relation_graph()`.
+///
+/// Relations from or to transactions that are not loaded are left out. So are relations that close a cycle:
+/// the graph is searched depth-first from the transactions in the order of their ids, following the relations
+/// in the order of the trace, and a relation that leads back to a transaction on the current path is dropped.
+/// All queries work on the remaining acyclic graph.
+pub struct RelationGraph<'a> {
+    ftr: &'a FTR,
+    // relation position and sink transaction by source transaction
+    outgoing: HashMap<usize, Vec<(usize, usize)>>,
+}
+
+/// A transaction in a chain.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Hop {
+    pub tx_id: usize,
+    /// Position of the relation that leads to this transaction in `FTR::relations()`, `None` for the first one
+    pub relation: Option<usize>,
+    pub start: BigUint,
+    pub end: BigUint,
+    /// Time from the end of the previous transaction to the start of this one, negative if they overlap
+    pub gap: BigInt,
+}
+
+impl Hop {
+    /// Time spent in this transaction.
+    pub fn duration(&self) -> BigUint {
+        if self.end > self.start { &self.end - &self.start } else { BigUint::default() }
+    }
+}
+
+/// Transactions that follow each other through relations.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Chain {
+    pub hops: Vec<Hop>,
+}
+
+impl Chain {
+    /// Time from the start of the first transaction to the end of the last one.
+    pub fn latency(&self) -> BigInt {
+        match (self.hops.first(), self.hops.last()) {
+            (Some(first), Some(last)) => BigInt::from(last.end.clone()) - BigInt::from(first.start.clone()),
+            _ => BigInt::default(),
+        }
+    }
+
+    /// Sum of the time spent in the transactions of the chain.
+    pub fn busy_time(&self) -> BigUint {
+        self.hops.iter().map(|hop| hop.duration()).sum()
+    }
+
+    pub fn tx_ids(&self) -> Vec<usize> {
+        self.hops.iter().map(|hop| hop.tx_id).collect()
+    }
+}
+
+// the latest end time of the chains from a transaction, with the length of the chain and the relation to follow for it
+struct ChainEnd {
+    end: BigUint,
+    length: usize,
+    next: Option<(usize, usize)>,
+}
+
+// the largest busy time of the chains from a transaction to the target, with the relation to follow for it
+struct BusiestPath {
+    time: BigUint,
+    next: Option<(usize, usize)>,
+}
+
+impl<'a> RelationGraph<'a> {
+    /// Builds the graph of the relations with one of the given names, or of all relations if `names` is empty.
+    pub(crate) fn new(ftr: &'a FTR, names: &[&str]) -> Self {
+        let mut outgoing: HashMap<usize, Vec<(usize, usize)>> = HashMap::new();
+        for (idx, rel) in ftr.tx_relations.iter().enumerate() {
+            let selected = names.is_empty() || names.iter().any(|name| **name == *rel.name);
+            if selected && ftr.find_transaction(rel.source_tx_id).is_some() && ftr.find_transaction(rel.sink_tx_id).is_some() {
+                outgoing.entry(rel.source_tx_id).or_default().push((idx, rel.sink_tx_id));
+            }
+        }
+        remove_cycles(&mut outgoing);
+        Self { ftr, outgoing }
+    }
+
+    /// Returns the relation positions and ids of the transactions that directly follow the transaction.
+    pub fn successors(&self, tx_id: usize) -> &[(usize, usize)] {
+        self.outgoing.get(&tx_id).map_or(&[], |successors| successors.as_slice())
+    }
+
+    /// Returns all chains that start at the transaction and end at a transaction without successors.
+    /// The number of chains can grow quickly with the number of relations per transaction.
+    pub fn chains_from(&self, tx_id: usize) -> Vec<Chain> {
+        let mut chains = vec![];
+        if self.ftr.find_transaction(tx_id).is_none() {
+            return chains;
+        }
+        // the path so far and the next successor to follow from each of its transactions
+        let mut path = vec![(None, tx_id)];
+        let mut next = vec![0];
+        while let (Some(&(_, tx_id)), Some(idx)) = (path.last(), next.last_mut()) {
+            let successors = self.successors(tx_id);
+            if successors.is_empty() {
+                chains.push(self.chain(&path));
+            }
+            match successors.get(*idx) {
+                Some((relation, sink)) => {
+                    *idx += 1;
+                    path.push((Some(*relation), *sink));
+                    next.push(0);
+                }
+                None => {
+                    path.pop();
+                    next.pop();
+                }
+            }
+        }
+        chains
+    }
+
+    /// Returns the chain from the transaction that ends last, i.e. the one with the largest latency.
+    /// Of chains that end at the same time, the one with more transactions is taken.
+    pub fn longest_chain(&self, tx_id: usize) -> Option<Chain> {
+        self.ftr.find_transaction(tx_id)?;
+        let mut best: HashMap<usize, ChainEnd> = HashMap::new();
+        for tx_id in self.post_order(tx_id) {
+            let mut result = ChainEnd { end: self.ftr.find_transaction(tx_id).unwrap().event.end_time.clone(), length: 1, next: None };
+            for (relation, sink) in self.successors(tx_id) {
+                let chain_end = &best[sink];
+                if (&chain_end.end, chain_end.length + 1) > (&result.end, result.length) {
+                    result = ChainEnd { end: chain_end.end.clone(), length: chain_end.length + 1, next: Some((*relation, *sink)) };
+                }
+            }
+            best.insert(tx_id, result);
+        }
+
+        let mut path = vec![(None, tx_id)];
+        while let Some(ChainEnd { next: Some((relation, sink)), .. }) = best.get(&path.last().unwrap().1) {
+            path.push((Some(*relation), *sink));
+        }
+        Some(self.chain(&path))
+    }
+
+    /// Returns the chain from `from` to `to` that spends the most time in its transactions,
+    /// or `None` if `to` cannot be reached from `from`.
+    pub fn critical_path(&self, from: usize, to: usize) -> Option<Chain> {
+        self.ftr.find_transaction(from)?;
+        self.ftr.find_transaction(to)?;
+        let mut best: HashMap<usize, Option<BusiestPath>> = HashMap::new();
+        for tx_id in self.post_order(from) {
+            let tx = self.ftr.find_transaction(tx_id).unwrap();
+            let duration = if tx.event.end_time > tx.event.start_time { &tx.event.end_time - &tx.event.start_time } else { BigUint::default() };
+            if tx_id == to {
+                best.insert(tx_id, Some(BusiestPath { time: duration, next: None }));
+                continue;
+            }
+            let mut result: Option<BusiestPath> = None;
+            for (relation, sink) in self.successors(tx_id) {
+                if let Some(path) = &best[sink] {
+                    if result.as_ref().is_none_or(|result| path.time > result.time) {
+                        result = Some(BusiestPath { time: path.time.clone(), next: Some((*relation, *sink)) });
+                    }
+                }
+            }
+            best.insert(tx_id, result.map(|path| BusiestPath { time: path.time + duration, next: path.next }));
+        }
+        best.get(&from)?.as_ref()?;
+
+        let mut path = vec![(None, from)];
+        while let Some(Some(BusiestPath { next: Some((relation, sink)), .. })) = best.get(&path.last().unwrap().1) {
+            path.push((Some(*relation), *sink));
+        }
+        Some(self.chain(&path))
+    }
+
+    // the transactions reachable from `tx_id`, each after all of its successors
+    fn post_order(&self, tx_id: usize) -> Vec<usize> {
+        let mut order = vec![];
+        let mut visited = HashSet::from([tx_id]);
+        let mut stack = vec![(tx_id, 0)];
+        while let Some((tx_id, idx)) = stack.last_mut() {
+            match self.successors(*tx_id).get(*idx) {
+                Some((_, sink)) => {
+                    *idx += 1;
+                    if visited.insert(*sink) {
+                        stack.push((*sink, 0));
+                    }
+                }
+                None => {
+                    order.push(*tx_id);
+                    stack.pop();
+                }
+            }
+        }
+        order
+    }
+
+    // turns relation positions and transaction ids into hops with the timing of the transactions
+    fn chain(&self, path: &[(Option<usize>, usize)]) -> Chain {
+        let mut hops: Vec<Hop> = vec![];
+        for (relation, tx_id) in path {
+            let event = &self.ftr.find_transaction(*tx_id).unwrap().event;
+            let gap = match hops.last() {
+                Some(previous) => BigInt::from(event.start_time.clone()) - BigInt::from(previous.end.clone()),
+                None => BigInt::default(),
+            };
+            hops.push(Hop { tx_id: *tx_id, relation: *relation, start: event.start_time.clone(), end: event.end_time.clone(), gap });
+        }
+        Chain { hops }
+    }
+}
+
+// drops the relations that close a cycle, so that every query works on the same acyclic graph.
+// The transactions are visited depth-first in the order of their ids, following the relations in the order of
+// the trace, and a relation back to a transaction on the current path is the one that is dropped.
+fn remove_cycles(outgoing: &mut HashMap<usize, Vec<(usize, usize)>>) {
+    let mut sources: Vec<usize> = outgoing.keys().copied().collect();
+    sources.sort();
+    let mut on_path: HashSet<usize> = HashSet::new();
+    let mut visited: HashSet<usize> = HashSet::new();
+    let mut back_relations: HashSet<usize> = HashSet::new();
+    for source in sources {
+        if !visited.insert(source) {
+            continue;
+        }
+        on_path.insert(source);
+        let mut stack = vec![(source, 0)];
+        while let Some((tx_id, idx)) = stack.last_mut() {
+            match outgoing.get(tx_id).and_then(|successors| successors.get(*idx)) {
+                Some(&(relation, sink)) => {
+                    *idx += 1;
+                    if on_path.contains(&sink) {
+                        back_relations.insert(relation);
+                    } else if visited.insert(sink) {
+                        on_path.insert(sink);
+                        stack.push((sink, 0));
+                    }
+                }
+                None => {
+                    on_path.remove(tx_id);
+                    stack.pop();
+                }
+            }
+        }
+    }
+    for successors in outgoing.values_mut() {
+        successors.retain(|(relation, _)| !back_relations.contains(relation));
+    }
+}
@@ -0,0 +1,127 @@
+use std::collections::{BTreeMap, HashMap};
+
+use regex::Regex;
+
+/// The design hierarchy of a trace, built from the stream names split at dots.
+/// A stream named `top.cpu.bus` is the scope `bus` inside `top.cpu`, and its generators are scopes inside it.
+#[derive(Debug, Default, Clone)]
+pub struct ScopeTree {
+    root: Scope,
+    // full paths of streams and generators
+    stream_ids: HashMap<String, usize>,
+    generator_ids: HashMap<String, usize>,
+}
+
+/// A node of the `ScopeTree`. Besides plain scopes of the hierarchy, nodes can stand for a stream or a generator.
+#[derive(Debug, Default, Clone)]
+pub struct Scope {
+    pub name: String,
+    /// Names of all scopes from the root to this one, joined with dots
+    pub path: String,
+    pub stream_id: Option<usize>,
+    pub generator_id: Option<usize>,
+    children: BTreeMap<String, Scope>,
+}
+
+impl Scope {
+    /// Returns the scopes directly inside this one, ordered by their names.
+    pub fn children(&self) -> impl Iterator<Item = &Scope> {
+        self.children.values()
+    }
+
+    pub fn child(&self, name: &str) -> Option<&Scope> {
+        self.children.get(name)
+    }
+
+    /// Returns the ids of all streams in this scope and the scopes inside it.
+    pub fn stream_ids(&self) -> Vec<usize> {
+        let mut ids = vec![];
+        self.visit(&mut |scope| ids.extend(scope.stream_id));
+        ids
+    }
+
+    // calls `f` for this scope and all scopes inside it, parents first
+    fn visit<'a>(&'a self, f: &mut impl FnMut(&'a Scope)) {
+        f(self);
+        for child in self.children.values() {
+            child.visit(f);
+        }
+    }
+
+    // returns the scope at the given path below this one, creating missing scopes on the way
+    fn get_or_create(&mut self, path: &str) -> &mut Scope {
+        let mut scope = self;
+        for name in path.split('.') {
+            let child_path = if scope.path.is_empty() { name.to_string() } else { format!("{}.{}", scope.path, name) };
+            scope = scope.children.entry(name.to_string()).or_insert_with(|| Scope {
+                name: name.to_string(),
+                path: child_path,
+                ..Scope::default()
+            });
+        }
+        scope
+    }
+}
+
+impl ScopeTree {
+    /// The unnamed scope that contains all others.
+    pub fn root(&self) -> &Scope {
+        &self.root
+    }
+
+    /// Returns the scope, stream or generator with the given path, e.g. `tr.addr_stream` or `tr.addr_stream.addr`.
+    pub fn get(&self, path: &str) -> Option<&Scope> {
+        path.split('.').try_fold(&self.root, |scope, name| scope.children.get(name))
+    }
+
+    /// Returns the id of the stream with the given name.
+    pub fn stream_id(&self, name: &str) -> Option<usize> {
+        self.stream_ids.get(name).copied()
+    }
+
+    /// Returns the id of the generator with the given path, which is the stream name and the generator name joined by a dot.
+    pub fn generator_id(&self, path: &str) -> Option<usize> {
+        self.generator_ids.get(path).copied()
+    }
+
+    /// Returns all scopes whose path matches the pattern, parents before their children.
+    /// In the pattern, `*` matches any part of a name, `**` any number of names and `?` a single character.
+    pub fn glob(&self, pattern: &str) -> color_eyre::Result<Vec<&Scope>> {
+        let mut regex = String::from("^");
+        let mut chars = pattern.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '*' if chars.peek() == Some(&'*') => {
+                    chars.next();
+                    regex.push_str(".*");
+                }
+                '*' => regex.push_str("[^.]*"),
+                '?' => regex.push_str("[^.]"),
+                c => regex.push_str(&regex::escape(&c.to_string())),
+            }
+        }
+        regex.push('$');
+        let regex = Regex::new(&regex)?;
+
+        let mut scopes = vec![];
+        for child in self.root.children.values() {
+            child.visit(&mut |scope| {
+                if regex.is_match(&scope.path) {
+                    scopes.push(scope);
+                }
+            });
+        }
+        Ok(scopes)
+    }
+
+    pub(crate) fn add_stream(&mut self, stream_id: usize, name: &str) {
+        self.root.get_or_create(name).stream_id = Some(stream_id);
+        self.stream_ids.insert(name.to_string(), stream_id);
+    }
+
+    pub(crate) fn add_generator(&mut self, gen_id: usize, stream_name: &str, name: &str) {
+        let path = format!("{}.{}", stream_name, name);
+        self.root.get_or_create(&path).generator_id = Some(gen_id);
+        self.generator_ids.insert(path, gen_id);
+    }
+}
@@ -0,0 +1,132 @@
+use std::collections::BTreeMap;
+
+use crate::types::{Transaction, TxSource, FTR};
+
+/// How transactions are split into groups for `FTR::grouped_stats()`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum GroupBy {
+    /// By the name of their generator, e.g. `read` and `write`
+    Generator,
+    /// By the value of the attribute with this name. Transactions without the attribute are left out.
+    Attribute(String),
+}
+
+/// Options for the duration statistics.
+#[derive(Debug, Clone)]
+pub struct StatsOptions {
+    buckets: usize,
+}
+
+impl Default for StatsOptions {
+    fn default() -> Self {
+        Self { buckets: 10 }
+    }
+}
+
+impl StatsOptions {
+    /// Histograms with 10 buckets.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of buckets of the duration histogram.
+    pub fn buckets(mut self, buckets: usize) -> Self {
+        self.buckets = buckets.max(1);
+        self
+    }
+}
+
+/// Statistics of the durations of a set of transactions, in the time scale of the trace.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DurationStats {
+    pub count: usize,
+    pub min: u64,
+    pub max: u64,
+    pub mean: f64,
+    /// Population standard deviation
+    pub stddev: f64,
+    pub p50: u64,
+    pub p90: u64,
+    pub p99: u64,
+    pub p999: u64,
+    pub histogram: Vec<HistogramBucket>,
+}
+
+/// Number of durations from `start` up to, but not including, `end`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HistogramBucket {
+    pub start: u64,
+    pub end: u64,
+    pub count: usize,
+}
+
+impl DurationStats {
+    /// Computes the statistics of the transactions, or returns `None` if there are none.
+    pub fn new<'a>(transactions: impl IntoIterator<Item = &'a Transaction>, options: &StatsOptions) -> Option<Self> {
+        let mut durations: Vec<u64> = transactions.into_iter().map(duration).collect();
+        if durations.is_empty() {
+            return None;
+        }
+        durations.sort_unstable();
+
+        let count = durations.len();
+        let (min, max) = (durations[0], durations[count - 1]);
+        let mean = durations.iter().map(|d| *d as f64).sum::<f64>() / count as f64;
+        let variance = durations.iter().map(|d| (*d as f64 - mean).powi(2)).sum::<f64>() / count as f64;
+
+        // nearest-rank percentiles
+        let percentile = |p: f64| durations[((p / 100.0 * count as f64).ceil() as usize).clamp(1, count) - 1];
+
+        // the range from min to max is split evenly, bucket i starts at the first duration that falls into it
+        let (buckets, range) = (options.buckets as u128, (max - min) as u128 + 1);
+        let bucket = |d: u64| (((d - min) as u128 * buckets / range) as usize).min(options.buckets - 1);
+        let edge = |i: u128| u64::try_from(min as u128 + (i * range).div_ceil(buckets)).unwrap_or(u64::MAX);
+        let mut histogram: Vec<HistogramBucket> = (0..buckets)
+            .map(|i| HistogramBucket { start: edge(i), end: edge(i + 1), count: 0 })
+            .collect();
+        for d in &durations {
+            histogram[bucket(*d)].count += 1;
+        }
+
+        Some(Self {
+            count,
+            min,
+            max,
+            mean,
+            stddev: variance.sqrt(),
+            p50: percentile(50.0),
+            p90: percentile(90.0),
+            p99: percentile(99.0),
+            p999: percentile(99.9),
+            histogram,
+        })
+    }
+}
+
+// durations that don't fit into an u64 are cut off
+fn duration(tx: &Transaction) -> u64 {
+    if tx.event.end_time <= tx.event.start_time {
+        return 0;
+    }
+    u64::try_from(&tx.event.end_time - &tx.event.start_time).unwrap_or(u64::MAX)
+}
+
+impl DurationStats {
+    // the statistics of every group of the transactions of the stream or generator
+    pub(crate) fn grouped(ftr: &FTR, source: TxSource, group_by: &GroupBy, options: &StatsOptions) -> BTreeMap<String, DurationStats> {
+        let mut groups: BTreeMap<String, Vec<&Transaction>> = BTreeMap::new();
+        for tx in ftr.source_transactions(source) {
+            let key = match group_by {
+                GroupBy::Generator => ftr.get_generator(tx.event.gen_id).map(|gen| gen.name.clone()),
+                GroupBy::Attribute(name) => ftr.attribute(tx, name).map(|attr| attr.value()),
+            };
+            if let Some(key) = key {
+                groups.entry(key).or_default().push(tx);
+            }
+        }
+        groups
+            .into_iter()
+            .filter_map(|(key, transactions)| Some((key, DurationStats::new(transactions, options)?)))
+            .collect()
+    }
+}
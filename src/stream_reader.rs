@@ -0,0 +1,65 @@
+use std::collections::VecDeque;
+use std::io::Read;
+
+use crate::cbor_decoder::{CborDecoder, SequentialReader};
+use crate::ftr_parser::{Chunk, FtrParser};
+use crate::types::{FTR, Transaction};
+
+/// Reads a FTR from an input that does not need to be seekable and hands out the transactions one by one,
+/// without keeping them in memory.
+///
+/// Streams, generators and relations are collected in the FTR returned by `ftr()` while reading,
+/// so they are only complete once the iterator is exhausted.
+/// Transactions only carry the relations that were read before their transaction block, which can be looked up in `ftr()`.
+pub struct FtrStreamReader<R: Read> {
+    ftr: FTR,
+    cbor_decoder: CborDecoder<SequentialReader<R>>,
+    pending: VecDeque<Transaction>,
+    finished: bool,
+}
+
+impl <R: Read> FtrStreamReader<R> {
+    pub(crate) fn new(reader: R) -> color_eyre::Result<Self> {
+        let mut cbor_decoder = CborDecoder::new(SequentialReader(reader));
+        FtrParser::parse_header(&mut cbor_decoder)?;
+
+        Ok(Self {
+            ftr: FTR::new(None),
+            cbor_decoder,
+            pending: VecDeque::new(),
+            finished: false,
+        })
+    }
+
+    /// The FTR data read so far, without any transactions.
+    pub fn ftr(&self) -> &FTR {
+        &self.ftr
+    }
+
+    pub fn into_ftr(self) -> FTR {
+        self.ftr
+    }
+}
+
+impl <R: Read> Iterator for FtrStreamReader<R> {
+    type Item = color_eyre::Result<Transaction>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.pending.is_empty() {
+            if self.finished {
+                return None;
+            }
+            let mut ftr_parser = FtrParser::new(&mut self.ftr);
+            match ftr_parser.parse_chunk(&mut self.cbor_decoder) {
+                Ok(Chunk::TxBlock(_, Some(transactions))) => self.pending.extend(transactions),
+                Ok(Chunk::End) => self.finished = true,
+                Ok(_) => {}
+                Err(e) => {
+                    self.finished = true;
+                    return Some(Err(e));
+                }
+            }
+        }
+        self.pending.pop_front().map(Ok)
+    }
+}
@@ -0,0 +1,158 @@
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::{ErrorKind, Seek, SeekFrom};
+use std::path::PathBuf;
+
+use crate::cbor_decoder::CborDecoder;
+use crate::ftr_parser::{Chunk, FtrParser};
+use crate::types::{FTR, RelationLoading};
+
+/// What changed in the FTR during a call to `FtrTailReader::refresh()`.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct TailUpdate {
+    pub new_streams: Vec<usize>,
+    pub new_generators: Vec<usize>,
+    /// Streams for which new transaction blocks were found.
+    /// If the transactions of such a stream are loaded, the new transactions have been loaded as well.
+    pub extended_streams: Vec<usize>,
+    /// Number of relations that were appended to `FTR::relations()`
+    pub new_relations: usize,
+    pub max_timestamp_changed: bool,
+    /// Set once the end of the trace has been reached, i.e. the file has been closed by the simulation.
+    pub finished: bool,
+}
+
+impl TailUpdate {
+    pub fn is_empty(&self) -> bool {
+        self.new_streams.is_empty() &&
+            self.new_generators.is_empty() &&
+            self.extended_streams.is_empty() &&
+            self.new_relations == 0 &&
+            !self.max_timestamp_changed
+    }
+}
+
+/// Follows a FTR file that is still being written, e.g. by a running simulation.
+///
+/// The reader remembers the offset after the last chunk that has been fully parsed.
+/// Each call to `refresh()` parses the chunks that were appended since, while incomplete chunks at the end of the file
+/// are left alone until the next call.
+pub struct FtrTailReader {
+    ftr: FTR,
+    offset: u64,
+    header_parsed: bool,
+    finished: bool,
+}
+
+impl FtrTailReader {
+    pub(crate) fn new(file_name: PathBuf) -> Self {
+        let mut ftr = FTR::new(Some(file_name));
+        // the relations of a growing file are parsed as they come in
+        ftr.relation_loading = RelationLoading::All;
+        Self {
+            ftr,
+            offset: 0,
+            header_parsed: false,
+            finished: false,
+        }
+    }
+
+    pub fn ftr(&self) -> &FTR {
+        &self.ftr
+    }
+
+    /// Mutable access to the FTR, e.g. for loading streams into memory.
+    pub fn ftr_mut(&mut self) -> &mut FTR {
+        &mut self.ftr
+    }
+
+    pub fn into_ftr(self) -> FTR {
+        self.ftr
+    }
+
+    /// File offset after the last chunk that has been fully parsed.
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    /// Parses all chunks that have been completely written since the last call and reports what changed.
+    pub fn refresh(&mut self) -> color_eyre::Result<TailUpdate> {
+        let mut update = TailUpdate::default();
+        if self.finished {
+            update.finished = true;
+            return Ok(update);
+        }
+
+        let mut file = File::open(self.ftr.path.as_ref().unwrap())?;
+        file.seek(SeekFrom::Start(self.offset))?;
+        let mut cbor_decoder = CborDecoder::new_at(&file, self.offset);
+
+        if !self.header_parsed {
+            match FtrParser::parse_header(&mut cbor_decoder) {
+                Ok(_) => {}
+                Err(e) if is_incomplete(&e) => return Ok(update),
+                Err(e) => return Err(e),
+            }
+            self.header_parsed = true;
+            self.offset = cbor_decoder.position();
+        }
+
+        let streams_before: HashSet<usize> = self.ftr.tx_streams.keys().cloned().collect();
+        let generators_before: HashSet<usize> = self.ftr.tx_generators.keys().cloned().collect();
+        let relations_before = self.ftr.tx_relations.len();
+        let max_timestamp_before = self.ftr.max_timestamp.clone();
+        // blocks of streams that are already in memory, these have to be loaded as well
+        let loaded_streams: HashSet<usize> = self.ftr.tx_streams.values().filter(|s| s.transactions_loaded).map(|s| s.id).collect();
+        let mut new_blocks = vec![];
+
+        loop {
+            let mut ftr_parser = FtrParser::new(&mut self.ftr);
+            match ftr_parser.parse_chunk(&mut cbor_decoder) {
+                Ok(Chunk::End) => {
+                    // the end of the indefinite top level array marks the end of the trace
+                    if let Ok(0xff) = cbor_decoder.peek() {
+                        self.finished = true;
+                        update.finished = true;
+                    }
+                    break;
+                }
+                Ok(Chunk::TxBlock(stream_id, _)) => {
+                    let stream = self.ftr.tx_streams.get(&stream_id).unwrap();
+                    if loaded_streams.contains(&stream_id) {
+                        new_blocks.push((stream_id, stream.tx_blocks.len() - 1));
+                    }
+                    if !update.extended_streams.contains(&stream_id) {
+                        update.extended_streams.push(stream_id);
+                    }
+                }
+                Ok(Chunk::Header) => {}
+                Err(e) if is_incomplete(&e) => break,
+                Err(e) => return Err(e),
+            }
+            self.offset = cbor_decoder.position();
+        }
+
+        let reader = File::open(self.ftr.path.as_ref().unwrap())?;
+        for (stream_id, block_idx) in new_blocks {
+            FtrParser::new(&mut self.ftr).load_tx_block(&reader, stream_id, block_idx)?;
+        }
+        for stream_id in update.extended_streams.iter().filter(|id| loaded_streams.contains(id)) {
+            self.ftr.index_stream(*stream_id);
+        }
+
+        update.new_streams = self.ftr.tx_streams.keys().filter(|id| !streams_before.contains(id)).cloned().collect();
+        update.new_streams.sort();
+        update.new_generators = self.ftr.tx_generators.keys().filter(|id| !generators_before.contains(id)).cloned().collect();
+        update.new_generators.sort();
+        update.extended_streams.sort();
+        update.new_relations = self.ftr.tx_relations.len() - relations_before;
+        update.max_timestamp_changed = self.ftr.max_timestamp != max_timestamp_before;
+
+        Ok(update)
+    }
+}
+
+// whether parsing failed because the input ended in the middle of a chunk
+fn is_incomplete(e: &color_eyre::Report) -> bool {
+    e.downcast_ref::<std::io::Error>().is_some_and(|e| e.kind() == ErrorKind::UnexpectedEof)
+}
@@ -0,0 +1,127 @@
+#[cfg(all(test, feature = "tokio"))]
+mod test {
+    use std::io::{Cursor, SeekFrom};
+    use std::path::PathBuf;
+    use std::pin::Pin;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::task::{Context, Poll};
+    use tokio::io::{AsyncRead, AsyncSeek, ReadBuf};
+    use crate::async_parse::AsyncFtrReader;
+    use crate::parse::parse_ftr;
+
+    #[tokio::test]
+    async fn async_parsing() {
+        for file in ["./example_files/my_db.ftr", "./example_files/my_db_c.ftr"] {
+            let mut expected = parse_ftr(PathBuf::from(file)).unwrap();
+            expected.load_relations().unwrap();
+            let mut ftr_reader = AsyncFtrReader::open(PathBuf::from(file)).await.unwrap();
+
+            assert_eq!(ftr_reader.ftr().max_timestamp, expected.max_timestamp);
+            assert_eq!(ftr_reader.ftr().tx_generators.len(), expected.tx_generators.len());
+            // relations are loaded together with their transactions
+            assert!(!ftr_reader.ftr().relations_loaded());
+            assert!(ftr_reader.ftr().tx_relations.is_empty());
+
+            let mut stream_ids: Vec<usize> = expected.tx_streams.keys().cloned().collect();
+            stream_ids.sort();
+            ftr_reader.load_stream_into_memory(stream_ids[1]).await.unwrap();
+            let relations = ftr_reader.ftr().tx_relations.len();
+            assert!(relations > 0 && relations < expected.tx_relations.len());
+
+            for stream_id in stream_ids {
+                expected.load_stream_into_memory(stream_id).unwrap();
+                ftr_reader.load_stream_into_memory(stream_id).await.unwrap();
+            }
+            for (id, gen) in &expected.tx_generators {
+                assert_eq!(ftr_reader.ftr().get_generator(*id).unwrap().transactions.len(), gen.transactions.len());
+                for (tx, expected_tx) in ftr_reader.ftr().get_generator(*id).unwrap().transactions.iter().zip(&gen.transactions) {
+                    assert_eq!(tx, expected_tx);
+                    assert_eq!(tx.outgoing_relations(ftr_reader.ftr()).count(), expected_tx.outgoing_relations(&expected).count());
+                    assert_eq!(tx.incoming_relations(ftr_reader.ftr()).count(), expected_tx.incoming_relations(&expected).count());
+                }
+            }
+
+            ftr_reader.load_relations().await.unwrap();
+            assert!(ftr_reader.ftr().relations_loaded());
+            assert_eq!(ftr_reader.ftr().tx_relations.len(), expected.tx_relations.len());
+        }
+    }
+
+    #[tokio::test]
+    async fn async_streaming() {
+        let bytes = tokio::fs::read("./example_files/my_db_c.ftr").await.unwrap();
+        let mut ftr_reader = AsyncFtrReader::new(Cursor::new(bytes)).await.unwrap();
+        let mut expected = parse_ftr(PathBuf::from("./example_files/my_db_c.ftr")).unwrap();
+        expected.load_stream_into_memory(1).unwrap();
+
+        let mut transactions = ftr_reader.transactions(1).unwrap();
+        let mut count = 0;
+        while let Some(tx) = transactions.next().await {
+            let tx = tx.unwrap();
+            assert!(expected.get_generator(tx.get_gen_id()).unwrap().transactions.contains(&tx));
+            count += 1;
+        }
+        assert_eq!(count, expected.get_stream(1).unwrap().generators.iter()
+            .map(|id| expected.get_generator(*id).unwrap().transactions.len())
+            .sum::<usize>());
+        assert!(ftr_reader.ftr().tx_generators.values().all(|g| g.transactions.is_empty()));
+    }
+
+    #[tokio::test]
+    async fn lazy_calls_without_file() {
+        let bytes = tokio::fs::read("./example_files/my_db.ftr").await.unwrap();
+        let mut ftr_reader = AsyncFtrReader::new(Cursor::new(bytes)).await.unwrap();
+        assert!(ftr_reader.ftr_mut().get_transaction(1).is_err());
+        assert!(ftr_reader.ftr_mut().follow_outgoing(1, None).is_err());
+        assert!(ftr_reader.ftr_mut().load_relations().is_err());
+
+        let mut stream_ids: Vec<usize> = ftr_reader.ftr().tx_streams.keys().cloned().collect();
+        stream_ids.sort();
+        for stream_id in stream_ids {
+            ftr_reader.load_stream_into_memory(stream_id).await.unwrap();
+        }
+        assert_eq!(ftr_reader.ftr_mut().get_transaction(1).unwrap().unwrap().get_tx_id(), 1);
+        assert!(ftr_reader.ftr_mut().get_transaction(100_000).unwrap().is_none());
+        assert!(!ftr_reader.ftr_mut().follow_outgoing(1, None).unwrap().is_empty());
+    }
+
+    // counts how often the input is seeked
+    struct CountingSeeks<R> {
+        inner: R,
+        seeks: Arc<AtomicUsize>,
+    }
+
+    impl<R: AsyncRead + Unpin> AsyncRead for CountingSeeks<R> {
+        fn poll_read(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<std::io::Result<()>> {
+            Pin::new(&mut self.inner).poll_read(cx, buf)
+        }
+    }
+
+    impl<R: AsyncSeek + Unpin> AsyncSeek for CountingSeeks<R> {
+        fn start_seek(mut self: Pin<&mut Self>, position: SeekFrom) -> std::io::Result<()> {
+            self.seeks.fetch_add(1, Ordering::Relaxed);
+            Pin::new(&mut self.inner).start_seek(position)
+        }
+
+        fn poll_complete(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<u64>> {
+            Pin::new(&mut self.inner).poll_complete(cx)
+        }
+    }
+
+    #[tokio::test]
+    async fn sequential_reads() {
+        for file in ["./example_files/my_db.ftr", "./example_files/my_db_c.ftr"] {
+            let bytes = tokio::fs::read(file).await.unwrap();
+            let seeks = Arc::new(AtomicUsize::new(0));
+            let ftr_reader = AsyncFtrReader::new(CountingSeeks { inner: Cursor::new(bytes), seeks: seeks.clone() }).await.unwrap();
+            // only the content of transaction blocks and relationship chunks is seeked over, unless it was read with the header
+            let skipped = ftr_reader.ftr().tx_streams.values().map(|stream| stream.tx_blocks.len()).sum::<usize>() + ftr_reader.ftr().relation_chunks.len();
+            assert!(seeks.load(Ordering::Relaxed) <= skipped);
+
+            let expected = parse_ftr(PathBuf::from(file)).unwrap();
+            assert_eq!(ftr_reader.ftr().str_dict, expected.str_dict);
+            assert_eq!(ftr_reader.ftr().tx_generators.len(), expected.tx_generators.len());
+        }
+    }
+}
@@ -0,0 +1,238 @@
+#[cfg(test)]
+mod test {
+    use crate::tests::common::load_all;
+    use crate::types::{Attribute, AttributeType, DataType};
+
+    #[test]
+    fn typed_accessors() {
+        let ftr = load_all("./example_files/my_db.ftr");
+        let tx = ftr.find_transaction(37).unwrap();
+
+        assert_eq!(tx.attribute("wr.addr").unwrap().value(), "191");
+        assert!(tx.attribute("addr").is_none());
+        assert!(tx.attribute_by_kind("wr.addr", AttributeType::BEGIN).is_some());
+        assert!(tx.attribute_by_kind("wr.addr", AttributeType::END).is_none());
+
+        assert_eq!(tx.get_u64("wr.addr").unwrap(), 191);
+        assert_eq!(tx.get_i64("wr.addr").unwrap(), 191);
+        assert_eq!(tx.get_f64("data_size").unwrap(), 24.0);
+        assert!(tx.get_u64("no_such_attribute").is_err());
+        assert!(tx.get_bool("wr.addr").is_err());
+        assert!(tx.get_str("wr.addr").is_err());
+        assert!(tx.get_time("wr.addr").is_err());
+
+        let attr = |data_type| Attribute::new_record("a".to_string(), data_type);
+        assert_eq!(attr(DataType::Integer(-1)).as_i64().unwrap(), -1);
+        assert!(attr(DataType::Integer(-1)).as_u64().is_err());
+        assert!(attr(DataType::Unsigned(u64::MAX)).as_i64().is_err());
+        assert_eq!(attr(DataType::BitVector("1010".to_string())).as_u64().unwrap(), 10);
+        assert_eq!(attr(DataType::BitVector("1010".to_string())).as_str().unwrap(), "1010");
+        assert!(attr(DataType::LogicVector("10x0".to_string())).as_u64().is_err());
+        assert!(attr(DataType::Boolean(true)).as_bool().unwrap());
+        assert_eq!(attr(DataType::Enumeration("IDLE".to_string())).as_str().unwrap(), "IDLE");
+        assert_eq!(attr(DataType::Time(5)).as_time().unwrap(), 5);
+        assert_eq!(attr(DataType::FloatingPointNumber(0.5)).as_f64().unwrap(), 0.5);
+        assert!(attr(DataType::Error).as_f64().is_err());
+    }
+
+    #[test]
+    fn attribute_slots() {
+        let ftr = load_all("./example_files/my_db.ftr");
+        assert_eq!(ftr.attribute_slot(5, "wr.addr"), Some(0));
+        assert_eq!(ftr.attribute_slot(5, "data_size"), Some(2));
+        assert_eq!(ftr.attribute_slot(5, "addr"), None);
+        assert_eq!(ftr.attribute_slot(42, "wr.addr"), None);
+
+        let gen = ftr.get_generator(5).unwrap();
+        for tx in &gen.transactions {
+            for name in ["wr.addr", "wr.data", "data_size", "addr"] {
+                assert_eq!(ftr.attribute(tx, name).map(|a| a.value()), tx.attribute(name).map(|a| a.value()));
+            }
+            assert_eq!(ftr.get_u64(tx, "wr.addr").unwrap(), tx.get_u64("wr.addr").unwrap());
+            assert_eq!(ftr.get_f64(tx, "data_size").unwrap(), tx.get_f64("data_size").unwrap());
+            assert!(ftr.get_u64(tx, "addr").is_err());
+            assert!(ftr.get_str(tx, "wr.addr").is_err());
+        }
+
+        // the getters of the FTR take the attribute at the slot, not the first one with the name
+        let mut tx = gen.transactions[0].clone();
+        let data_size = tx.get_u64("data_size").unwrap();
+        tx.attributes[0] = Attribute::new_record("data_size".to_string(), DataType::Unsigned(data_size + 1));
+        assert_eq!(tx.get_u64("data_size").unwrap(), data_size + 1);
+        assert_eq!(ftr.get_u64(&tx, "data_size").unwrap(), data_size);
+        assert!(ftr.get_u64(&tx, "wr.addr").is_err());
+
+        // transactions with another order of attributes are still found
+        let mut tx = gen.transactions[0].clone();
+        tx.attributes.reverse();
+        assert_eq!(ftr.attribute(&tx, "wr.addr").unwrap().value(), "191");
+        assert_eq!(ftr.get_i64(&tx, "wr.addr").unwrap(), 191);
+    }
+
+    #[test]
+    fn deserialize_attributes() {
+        use serde::Deserialize;
+        use crate::de::AttributeOptions;
+        use crate::types::Transaction;
+
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Read {
+            addr: u32,
+            data_size: u8,
+            data: u64,
+        }
+
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct WriteFlat {
+            #[serde(rename = "wr.addr")]
+            addr: u64,
+            data_size: f64,
+            missing: Option<u64>,
+        }
+
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Wr {
+            addr: u64,
+            data: u64,
+        }
+
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct WriteNested {
+            wr: Wr,
+        }
+
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct DataSize {
+            data_size: u8,
+        }
+
+        let ftr = load_all("./example_files/my_db.ftr");
+        let read = ftr.find_transaction(7).unwrap();
+        assert_eq!(read.deserialize_attributes::<Read>().unwrap(), Read { addr: 1, data_size: 24, data: 1 });
+
+        let write = ftr.find_transaction(37).unwrap();
+        assert_eq!(write.deserialize_attributes::<WriteFlat>().unwrap(), WriteFlat { addr: 191, data_size: 24.0, missing: None });
+        let nested = write.deserialize_attributes_with::<WriteNested>(&AttributeOptions::new().nested(true)).unwrap();
+        assert_eq!(nested, WriteNested { wr: Wr { addr: 191, data: 1 } });
+
+        // attributes of other kinds are left out
+        let begin = AttributeOptions::new().kind(AttributeType::BEGIN);
+        assert!(write.deserialize_attributes_with::<DataSize>(&begin).is_err());
+        let record = AttributeOptions::new().kind(AttributeType::BEGIN).kind(AttributeType::RECORD);
+        assert_eq!(write.deserialize_attributes_with::<DataSize>(&record).unwrap(), DataSize { data_size: 24 });
+
+        #[derive(Debug, Deserialize, PartialEq)]
+        enum Cmd { Read, Write }
+
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Typed<'a> {
+            cmd: Cmd,
+            name: &'a str,
+            mask: u8,
+            valid: bool,
+            offset: i32,
+        }
+
+        let mut tx: Transaction = read.clone();
+        tx.attributes = vec![
+            Attribute::new_begin("cmd".to_string(), DataType::Enumeration("Write".to_string())),
+            Attribute::new_begin("name".to_string(), DataType::String("burst".to_string())),
+            Attribute::new_begin("mask".to_string(), DataType::BitVector("1010".to_string())),
+            Attribute::new_record("valid".to_string(), DataType::Boolean(true)),
+            Attribute::new_end("offset".to_string(), DataType::Integer(-4)),
+        ];
+        let typed: Typed = tx.deserialize_attributes().unwrap();
+        assert_eq!(typed, Typed { cmd: Cmd::Write, name: "burst", mask: 10, valid: true, offset: -4 });
+
+        tx.attributes[4].data_type = DataType::Unsigned(u64::MAX);
+        assert!(tx.deserialize_attributes::<Typed>().is_err());
+    }
+
+    #[test]
+    fn attribute_tree() {
+        use serde::Deserialize;
+        use crate::attribute_tree::AttributeNode;
+        use crate::de::AttributeOptions;
+
+        let ftr = load_all("./example_files/my_db.ftr");
+        let mut tx = ftr.find_transaction(37).unwrap().clone();
+        let tree = tx.attribute_tree();
+        assert_eq!(tree.get("wr.addr").unwrap().attribute().unwrap().value(), "191");
+        assert_eq!(tree.to_string(), "wr\n  addr = 191\n  data = 1\ndata_size = 24\n");
+
+        let unsigned = |name: &str, value| Attribute::new_begin(name.to_string(), DataType::Unsigned(value));
+        tx.attributes = vec![
+            unsigned("trans.address", 16),
+            unsigned("trans.data[1]", 2),
+            unsigned("trans.data[0]", 1),
+            unsigned("trans.data[3]", 4),
+            Attribute::new_end("trans.response_status".to_string(), DataType::Enumeration("OK".to_string())),
+            unsigned("trans.address.high", 0),
+            unsigned("matrix[1][0]", 3),
+            unsigned("odd[name", 5),
+            Attribute::new_end("trans.address".to_string(), DataType::Unsigned(17)),
+        ];
+        let tree = tx.attribute_tree();
+        assert_eq!(tree.get("trans.data[1]").unwrap().attribute().unwrap().value(), "2");
+        assert!(tree.get("trans.data[2]").is_none());
+        assert!(tree.get("trans.data[9]").is_none());
+        assert!(tree.get("trans.address.high").is_none());
+        assert_eq!(tree.get("trans.address").unwrap().attribute().unwrap().value(), "16");
+        assert_eq!(tree.get("matrix[1][0]").unwrap().attribute().unwrap().value(), "3");
+        assert_eq!(tree.field("odd[name").unwrap().attribute().unwrap().value(), "5");
+        assert!(matches!(tree.get("trans.data"), Some(AttributeNode::Array(elements)) if elements.len() == 3));
+        assert_eq!(tree.to_string(), "trans\n  address = 16\n  data\n    [0] = 1\n    [1] = 2\n    [3] = 4\n  response_status = OK\nmatrix\n  [1]\n    [0] = 3\nodd[name = 5\n");
+
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Trans {
+            address: u64,
+            data: Vec<Option<u8>>,
+            response_status: String,
+        }
+
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Payload {
+            trans: Trans,
+        }
+
+        let payload: Payload = tx.deserialize_attributes_with(&AttributeOptions::new().nested(true)).unwrap();
+        assert_eq!(payload.trans, Trans { address: 16, data: vec![Some(1), Some(2), None, Some(4)], response_status: "OK".to_string() });
+        let end = AttributeNode::new(tx.attributes.iter().filter(|attr| attr.kind == AttributeType::END));
+        assert_eq!(end.get("trans.address").unwrap().attribute().unwrap().value(), "17");
+
+        // the shape of the tree doesn't depend on the other attributes
+        tx.attributes = vec![unsigned("trans.data[5]", 6)];
+        let tree = tx.attribute_tree();
+        assert_eq!(tree.to_string(), "trans\n  data\n    [5] = 6\n");
+        assert_eq!(tree.get("trans.data[5]").unwrap().attribute().unwrap().value(), "6");
+
+        // an index from the file must not decide how much memory is allocated
+        tx.attributes = vec![
+            unsigned("trans.data[18446744073709551615]", 1),
+            unsigned("trans.data[4000000000]", 2),
+            unsigned("trans.data[1]", 3),
+            unsigned("trans.data[18446744073709551616]", 4),
+        ];
+        let tree = tx.attribute_tree();
+        assert_eq!(tree.get("trans.data[18446744073709551615]").unwrap().attribute().unwrap().value(), "1");
+        assert_eq!(tree.get("trans.data[4000000000]").unwrap().attribute().unwrap().value(), "2");
+        assert_eq!(tree.get("trans.data[1]").unwrap().attribute().unwrap().value(), "3");
+        assert!(matches!(tree.get("trans.data"), Some(AttributeNode::Array(elements)) if elements.len() == 3));
+        // parts in brackets that are no `usize` are field names
+        assert_eq!(tree.get("trans").unwrap().field("data[18446744073709551616]").unwrap().attribute().unwrap().value(), "4");
+        assert!(tx.deserialize_attributes_with::<serde::de::IgnoredAny>(&AttributeOptions::new().nested(true)).is_ok());
+
+        // large arrays are read as maps, small ones as sequences
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Sparse {
+            data: std::collections::BTreeMap<usize, u8>,
+        }
+        tx.attributes.truncate(3);
+        let sparse: std::collections::BTreeMap<String, Sparse> = tx.deserialize_attributes_with(&AttributeOptions::new().nested(true)).unwrap();
+        assert_eq!(sparse["trans"].data, [(1, 3), (4_000_000_000, 2), (usize::MAX, 1)].into_iter().collect());
+        tx.attributes = vec![unsigned("data[65535]", 1)];
+        let data: std::collections::BTreeMap<String, Vec<Option<u8>>> = tx.deserialize_attributes_with(&AttributeOptions::new().nested(true)).unwrap();
+        assert_eq!(data["data"].len(), crate::de::MAX_SEQUENCE_LEN);
+        assert_eq!(data["data"][65535], Some(1));
+    }
+}
@@ -0,0 +1,166 @@
+// helpers shared by the tests
+#![cfg(test)]
+
+use std::path::PathBuf;
+
+use crate::parse::parse_ftr;
+use crate::types::{Event, Transaction, FTR};
+
+/// Parses the file and loads all of its relations and streams.
+pub(crate) fn load_all(file: &str) -> FTR {
+    let mut ftr = parse_ftr(PathBuf::from(file)).unwrap();
+    ftr.load_relations().unwrap();
+    for stream_id in ftr.tx_streams.keys().cloned().collect::<Vec<_>>() {
+        ftr.load_stream_into_memory(stream_id).unwrap();
+    }
+    ftr
+}
+
+/// Transactions without attributes of generator 4, with ids from 1 on, that start and end at the given times.
+pub(crate) fn transactions_from_pairs(pairs: &[(u64, u64)]) -> Vec<Transaction> {
+    pairs
+        .iter()
+        .enumerate()
+        .map(|(idx, (start, end))| Transaction {
+            event: Event { tx_id: idx + 1, gen_id: 4, start_time: (*start).into(), end_time: (*end).into() },
+            attributes: vec![],
+            inc_relations: vec![],
+            out_relations: vec![],
+        })
+        .collect()
+}
+
+// major type and argument of the CBOR data item at `pos`, the argument is `None` for indefinite lengths
+fn read_head(bytes: &[u8], pos: &mut usize) -> (u8, Option<u64>) {
+    let ib = bytes[*pos];
+    *pos += 1;
+    let argument = match ib & 0x1f {
+        length @ 0..=23 => Some(length as u64),
+        length @ 24..=27 => {
+            let size = 1 << (length - 24);
+            let argument = bytes[*pos..*pos + size].iter().fold(0, |value, b| value << 8 | *b as u64);
+            *pos += size;
+            Some(argument)
+        }
+        _ => None,
+    };
+    (ib >> 5, argument)
+}
+
+fn skip_item(bytes: &[u8], pos: &mut usize) {
+    let (major_type, argument) = read_head(bytes, pos);
+    let items = match (major_type, argument) {
+        (2 | 3, Some(len)) => {
+            *pos += len as usize;
+            0
+        }
+        (4, Some(len)) => len,
+        (5, Some(len)) => 2 * len,
+        (6, _) => 1,
+        (4 | 5, None) => {
+            while bytes[*pos] != 0xff {
+                skip_item(bytes, pos);
+            }
+            *pos += 1;
+            0
+        }
+        _ => 0,
+    };
+    for _ in 0..items {
+        skip_item(bytes, pos);
+    }
+}
+
+/// Appends the head of a CBOR data item with the given major type and argument.
+pub(crate) fn write_head(out: &mut Vec<u8>, major_type: u8, argument: u64) {
+    let major_type = major_type << 5;
+    match argument {
+        0..=23 => out.push(major_type | argument as u8),
+        _ => {
+            out.push(major_type | 27);
+            out.extend(argument.to_be_bytes());
+        }
+    }
+}
+
+/// Adds an uncompressed chunk with the given tag and CBOR content at the end of an FTR file.
+pub(crate) fn append_chunk(bytes: &[u8], tag: u64, content: &[u8]) -> Vec<u8> {
+    // the indefinite array of chunks ends with a break
+    let mut out = bytes[..bytes.len() - 1].to_vec();
+    write_head(&mut out, 6, tag);
+    write_head(&mut out, 2, content.len() as u64);
+    out.extend(content);
+    out.push(0xff);
+    out
+}
+
+/// Splits the transaction blocks of an uncompressed FTR file into blocks of at most `block_size` transactions,
+/// with the start and end time of each block taken from its transactions.
+pub(crate) fn split_tx_blocks(bytes: &[u8], block_size: usize) -> Vec<u8> {
+    rewrite_tx_blocks(bytes, block_size, |_, start, end| (start, end))
+}
+
+/// Same as `split_tx_blocks()`, but replaces the start and end time of every transaction by what `times` returns
+/// for its id, start and end time.
+pub(crate) fn rewrite_tx_blocks(bytes: &[u8], block_size: usize, times: impl Fn(u64, u64, u64) -> (u64, u64)) -> Vec<u8> {
+    // tag 55799 and the start of the indefinite array of chunks
+    let mut pos = 4;
+    let mut out = bytes[..pos].to_vec();
+    while bytes[pos] != 0xff {
+        let chunk_start = pos;
+        let (_, tag) = read_head(bytes, &mut pos);
+        if tag != Some(12) {
+            skip_item(bytes, &mut pos);
+            out.extend(&bytes[chunk_start..pos]);
+            continue;
+        }
+        read_head(bytes, &mut pos);
+        let (_, stream_id) = read_head(bytes, &mut pos);
+        read_head(bytes, &mut pos);
+        read_head(bytes, &mut pos);
+        let (_, len) = read_head(bytes, &mut pos);
+        let payload = &bytes[pos..pos + len.unwrap() as usize];
+        pos += payload.len();
+
+        // every transaction is an array that starts with the tagged event of id, generator, start and end time
+        let mut transactions = vec![];
+        let mut tx_pos = 1;
+        while payload[tx_pos] != 0xff {
+            let tx_start = tx_pos;
+            let mut event_pos = tx_pos;
+            read_head(payload, &mut event_pos);
+            read_head(payload, &mut event_pos);
+            read_head(payload, &mut event_pos);
+            let (_, tx_id) = read_head(payload, &mut event_pos);
+            read_head(payload, &mut event_pos);
+            let times_start = event_pos;
+            let start = read_head(payload, &mut event_pos).1.unwrap();
+            let end = read_head(payload, &mut event_pos).1.unwrap();
+            skip_item(payload, &mut tx_pos);
+
+            let (start, end) = times(tx_id.unwrap(), start, end);
+            let mut tx = payload[tx_start..times_start].to_vec();
+            write_head(&mut tx, 0, start);
+            write_head(&mut tx, 0, end);
+            tx.extend(&payload[event_pos..tx_pos]);
+            transactions.push((tx, start, end));
+        }
+
+        for block in transactions.chunks(block_size) {
+            let mut content = vec![0x9f];
+            for (tx, _, _) in block {
+                content.extend(tx);
+            }
+            content.push(0xff);
+            write_head(&mut out, 6, 12);
+            write_head(&mut out, 4, 4);
+            write_head(&mut out, 0, stream_id.unwrap());
+            write_head(&mut out, 0, block.iter().map(|(_, start, _)| *start).min().unwrap());
+            write_head(&mut out, 0, block.iter().map(|(_, _, end)| *end).max().unwrap());
+            write_head(&mut out, 2, content.len() as u64);
+            out.extend(content);
+        }
+    }
+    out.extend(&bytes[pos..]);
+    out
+}
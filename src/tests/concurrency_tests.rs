@@ -0,0 +1,54 @@
+#[cfg(test)]
+mod test {
+    use num_bigint::BigUint;
+    use crate::concurrency::ConcurrencyProfile;
+    use crate::tests::common::{load_all, transactions_from_pairs};
+    use crate::types::TxSource;
+
+    #[test]
+    fn concurrency_profile() {
+        let ftr = load_all("./example_files/my_db.ftr");
+        let in_flight = |gen_ids: &[usize], time: &BigUint| gen_ids
+            .iter()
+            .flat_map(|gen_id| &ftr.tx_generators[gen_id].transactions)
+            .filter(|tx| tx.event.start_time <= *time && *time < tx.event.end_time)
+            .count();
+
+        for (source, gen_ids) in [(TxSource::Stream(1), vec![4, 5]), (TxSource::Stream(3), vec![7, 8]), (TxSource::Generator(6), vec![6])] {
+            let profile = ftr.concurrency(source);
+            let steps = profile.steps();
+            assert!(!steps.is_empty());
+            assert_eq!(steps.last().unwrap().count, 0);
+            assert!(steps.windows(2).all(|pair| pair[0].time < pair[1].time && pair[0].count != pair[1].count));
+            for step in steps {
+                assert_eq!(step.count, in_flight(&gen_ids, &step.time));
+                assert_eq!(profile.at(&step.time), step.count);
+            }
+            assert_eq!(profile.max(), steps.iter().map(|step| in_flight(&gen_ids, &step.time)).max().unwrap());
+            assert!(profile.average() > 0.0 && profile.average() <= profile.max() as f64);
+
+            for (start, end) in profile.intervals_above(0) {
+                assert!(start < end);
+                assert!(in_flight(&gen_ids, &start) > 0);
+                assert_eq!(in_flight(&gen_ids, &end), 0);
+            }
+            assert!(profile.intervals_above(profile.max()).is_empty());
+        }
+        assert!(ftr.concurrency(TxSource::Stream(42)).steps().is_empty());
+    }
+
+    #[test]
+    fn concurrency_step_function() {
+        let transactions = transactions_from_pairs(&[(0, 10), (5, 15), (5, 8), (20, 30)]);
+        let profile = ConcurrencyProfile::new(&transactions);
+        let steps: Vec<(u64, usize)> = profile.steps().iter().map(|step| (u64::try_from(&step.time).unwrap(), step.count)).collect();
+        assert_eq!(steps, vec![(0, 1), (5, 3), (8, 2), (10, 1), (15, 0), (20, 1), (30, 0)]);
+        assert_eq!(profile.max(), 3);
+        assert_eq!(profile.at(&BigUint::from(9u64)), 2);
+        assert_eq!(profile.at(&BigUint::from(40u64)), 0);
+        // 5 * 1 + 3 * 3 + 2 * 2 + 5 * 1 + 10 * 1 over 30
+        assert!((profile.average() - 33.0 / 30.0).abs() < 1e-9);
+        assert_eq!(profile.intervals_above(1), vec![(BigUint::from(5u64), BigUint::from(10u64))]);
+        assert_eq!(profile.intervals_above(0).len(), 2);
+    }
+}
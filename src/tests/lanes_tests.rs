@@ -0,0 +1,35 @@
+#[cfg(test)]
+mod test {
+    use crate::lanes::LaneAssignment;
+    use crate::tests::common::{load_all, transactions_from_pairs};
+    use crate::types::TxSource;
+
+    #[test]
+    fn lane_assignment() {
+        let ftr = load_all("./example_files/my_db.ftr");
+        for (stream_id, gen_ids) in [(1, vec![4, 5]), (2, vec![6]), (3, vec![7, 8])] {
+            let lanes = ftr.lanes(TxSource::Stream(stream_id));
+            let transactions: Vec<_> = gen_ids.iter().flat_map(|gen_id| &ftr.tx_generators[gen_id].transactions).collect();
+            for a in &transactions {
+                assert!(lanes.lane(a.event.tx_id).unwrap() < lanes.num_lanes());
+                for b in &transactions {
+                    let overlap = a.event.start_time < b.event.end_time && b.event.start_time < a.event.end_time;
+                    if a.event.tx_id != b.event.tx_id && overlap {
+                        assert_ne!(lanes.lane(a.event.tx_id), lanes.lane(b.event.tx_id));
+                    }
+                }
+            }
+            // greedy coloring of intervals needs exactly as many lanes as transactions overlap at most
+            assert_eq!(lanes.num_lanes(), ftr.concurrency(TxSource::Stream(stream_id)).max());
+            assert_eq!(lanes, ftr.lanes(TxSource::Stream(stream_id)));
+        }
+        assert!(ftr.lanes(TxSource::Stream(1)).lane(1_000_000).is_none());
+        assert_eq!(ftr.lanes(TxSource::Stream(42)).num_lanes(), 0);
+
+        let transactions = transactions_from_pairs(&[(0, 10), (2, 4), (4, 12), (10, 11), (3, 5)]);
+        let lanes = LaneAssignment::new(&transactions);
+        let assigned: Vec<usize> = transactions.iter().map(|tx| lanes.lane(tx.event.tx_id).unwrap()).collect();
+        assert_eq!(assigned, vec![0, 1, 1, 0, 2]);
+        assert_eq!(lanes.num_lanes(), 3);
+    }
+}
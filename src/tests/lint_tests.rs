@@ -0,0 +1,109 @@
+#[cfg(test)]
+mod test {
+    use std::path::PathBuf;
+    use crate::lint::{Check, Location, Severity};
+    use crate::parse::parse_ftr;
+    use crate::tests::common::load_all;
+    use crate::types::TxRelation;
+
+    #[test]
+    fn clean_traces() {
+        for file in ["./example_files/my_db.ftr", "./example_files/my_db_c.ftr"] {
+            assert!(load_all(file).lint().is_empty());
+        }
+    }
+
+    #[test]
+    fn skipped_checks() {
+        let mut ftr = parse_ftr(PathBuf::from("./example_files/my_db.ftr")).unwrap();
+        let findings = ftr.lint();
+        assert_eq!(findings.len(), 1);
+        assert_eq!((findings[0].check, findings[0].location, findings[0].severity), (Check::Skipped, Location::Trace, Severity::Info));
+        assert_eq!(findings[0].to_string(), "info: trace: streams 1, 2, 3 are not loaded, so their transactions, the relations and the dictionary entries are not checked");
+
+        for stream_id in [1, 2, 3] {
+            ftr.load_stream_into_memory(stream_id).unwrap();
+        }
+        let findings = ftr.lint();
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].to_string(), "info: trace: not all relations are loaded, so the relations and the dictionary entries are not checked");
+
+        ftr.load_relations().unwrap();
+        assert!(ftr.lint().is_empty());
+    }
+
+    #[test]
+    fn broken_trace() {
+        let mut ftr = load_all("./example_files/my_db.ftr");
+        let transactions = &mut ftr.tx_generators.get_mut(&4).unwrap().transactions;
+        transactions[0].event.start_time = 10u64.into();
+        transactions[0].event.end_time = 5u64.into();
+        transactions[1].event.end_time = transactions[1].event.start_time.clone();
+        transactions[2].event.end_time = 4_000_000u64.into();
+        let mut duplicate = transactions[3].clone();
+        duplicate.event.start_time = 0u64.into();
+        duplicate.event.end_time = 1u64.into();
+        transactions.push(duplicate);
+        let (first, second, third, fourth) = (transactions[0].event.tx_id, transactions[1].event.tx_id, transactions[2].event.tx_id, transactions[3].event.tx_id);
+
+        ftr.tx_relations.push(TxRelation {
+            name: "addr_phase".into(),
+            source_tx_id: first,
+            sink_tx_id: 1_000_000,
+            source_stream_id: Some(1),
+            sink_stream_id: None,
+        });
+        ftr.str_dict.insert(1_000, "read".to_string());
+        ftr.tx_streams.get_mut(&2).unwrap().tx_blocks[0].end_time = 0;
+
+        let findings = ftr.lint();
+        let found = |check: Check, location: Location| findings.iter().find(|finding| finding.check == check && finding.location == location);
+
+        assert_eq!(found(Check::NegativeDuration, Location::Transaction { tx_id: first, gen_id: 4 }).unwrap().severity, Severity::Error);
+        assert_eq!(found(Check::ZeroDuration, Location::Transaction { tx_id: second, gen_id: 4 }).unwrap().severity, Severity::Warning);
+        assert_eq!(found(Check::BeyondMaxTimestamp, Location::Transaction { tx_id: third, gen_id: 4 }).unwrap().severity, Severity::Error);
+        assert_eq!(found(Check::DuplicateTxId, Location::Transaction { tx_id: fourth, gen_id: 4 }).unwrap().severity, Severity::Error);
+        let relation = found(Check::UnknownRelationTx, Location::Relation(ftr.tx_relations.len() - 1)).unwrap();
+        assert_eq!(relation.to_string(), format!("error: relation {}: sink transaction 1000000 does not exist", ftr.tx_relations.len() - 1));
+        assert_eq!(found(Check::UnusedDictionaryEntry, Location::DictionaryEntry(1_000)).unwrap().severity, Severity::Info);
+        assert_eq!(found(Check::TxBlockBounds, Location::TxBlock { stream_id: 2, block_idx: 0 }).unwrap().severity, Severity::Warning);
+        // blocks are checked against their transactions as they were read
+        assert!(found(Check::TxBlockBounds, Location::TxBlock { stream_id: 1, block_idx: 0 }).is_none());
+
+        assert_eq!(findings.iter().map(|finding| finding.severity).max(), Some(Severity::Error));
+    }
+
+    #[test]
+    fn broken_file() {
+        use std::fs;
+        use crate::tests::common::{append_chunk, write_head};
+
+        // a generator of the unknown stream 42 and a second dictionary entry for a name that is in use
+        let bytes = fs::read("./example_files/my_db.ftr").unwrap();
+        let name_id = *parse_ftr(PathBuf::from("./example_files/my_db.ftr")).unwrap().str_dict.iter().find(|(_, s)| *s == "read").unwrap().0;
+        let mut dictionary = vec![];
+        write_head(&mut dictionary, 5, 1);
+        write_head(&mut dictionary, 0, 1_000);
+        write_head(&mut dictionary, 3, 4);
+        dictionary.extend(b"read");
+        let mut directory = vec![0x9f];
+        write_head(&mut directory, 6, 17);
+        write_head(&mut directory, 4, 3);
+        for value in [99, name_id as u64, 42] {
+            write_head(&mut directory, 0, value);
+        }
+        directory.push(0xff);
+        let bytes = append_chunk(&append_chunk(&bytes, 8, &dictionary), 10, &directory);
+        let path = std::env::temp_dir().join(format!("ftr_parser_test_{}_lint.ftr", std::process::id()));
+        fs::write(&path, bytes).unwrap();
+
+        let ftr = load_all(path.to_str().unwrap());
+        assert_eq!(ftr.get_generator(99).unwrap().stream_id, 42);
+        let findings = ftr.lint();
+        assert_eq!(findings.len(), 2);
+        assert_eq!((findings[0].check, findings[0].location), (Check::UnknownStream, Location::Generator(99)));
+        assert_eq!(findings[0].to_string(), "error: generator 99: stream 42 does not exist");
+        assert_eq!((findings[1].check, findings[1].location), (Check::UnusedDictionaryEntry, Location::DictionaryEntry(1_000)));
+        fs::remove_file(path).unwrap();
+    }
+}
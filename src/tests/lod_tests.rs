@@ -0,0 +1,54 @@
+#[cfg(test)]
+mod test {
+    use std::path::PathBuf;
+    use num_bigint::BigUint;
+    use crate::parse::{parse_ftr, read_from_bytes};
+    use crate::types::TxSource;
+
+    #[test]
+    fn lod_summaries() {
+        let mut ftr = parse_ftr(PathBuf::from("./example_files/my_db.ftr")).unwrap();
+        assert!(ftr.lod_summary(1).is_none());
+        ftr.load_stream_into_memory(1).unwrap();
+        let summary = ftr.lod_summary(1).unwrap();
+        assert_eq!(summary.len(), 18);
+
+        let transactions: Vec<_> = ftr.tx_generators[&4].transactions.iter().chain(&ftr.tx_generators[&5].transactions).collect();
+        let busy = ftr.concurrency(TxSource::Stream(1)).intervals_above(0);
+        for buckets in [1, 3, 8, 100] {
+            let lod = summary.query(0u64, 3_400_000u64, buckets);
+            assert_eq!(lod.len(), buckets);
+            for bucket in &lod {
+                let starting: Vec<_> = transactions.iter().filter(|tx| bucket.start <= tx.event.start_time && tx.event.start_time < bucket.end).collect();
+                let durations: Vec<u64> = starting.iter().map(|tx| u64::try_from(&tx.event.end_time - &tx.event.start_time).unwrap()).collect();
+                assert_eq!(bucket.count, starting.len());
+                assert_eq!(bucket.min_duration, durations.iter().min().copied());
+                assert_eq!(bucket.max_duration, durations.iter().max().copied());
+                let coverage: BigUint = busy
+                    .iter()
+                    .map(|(start, end)| {
+                        let (from, to) = (start.max(&bucket.start), end.min(&bucket.end));
+                        if from < to { to - from } else { BigUint::default() }
+                    })
+                    .sum();
+                assert_eq!(BigUint::from(bucket.coverage), coverage);
+                let reads = starting.iter().filter(|tx| tx.event.gen_id == 4).count();
+                let expected = if starting.is_empty() { None } else if reads >= starting.len() - reads { Some(4) } else { Some(5) };
+                assert_eq!(bucket.dominant_generator, expected);
+            }
+        }
+        let whole = &summary.query(0u64, 3_400_000u64, 1)[0];
+        assert_eq!(whole.count, 18);
+        assert_eq!(whole.dominant_generator, Some(4));
+        assert!(summary.query(10_000_000u64, 20_000_000u64, 4).iter().all(|bucket| bucket.count == 0 && bucket.coverage == 0));
+        assert!(summary.query(5u64, 5u64, 4).is_empty());
+
+        ftr.drop_stream_from_memory(1);
+        assert!(ftr.lod_summary(1).is_none());
+
+        let in_memory = read_from_bytes(std::fs::read("./example_files/my_db.ftr").unwrap()).unwrap();
+        for stream_id in 1..=3 {
+            assert_eq!(in_memory.lod_summary(stream_id).unwrap().len(), in_memory.tx_streams[&stream_id].generators.iter().map(|id| in_memory.tx_generators[id].transactions.len()).sum::<usize>());
+        }
+    }
+}
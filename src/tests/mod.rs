@@ -1 +1,14 @@
-mod parser_tests;
\ No newline at end of file
+// the original parsing tests only print what they load and ignore the results
+#[allow(unused_must_use)]
+mod parser_tests;
+mod tail_reader_tests;
+mod async_tests;
+mod query_tests;
+mod attribute_tests;
+mod stats_tests;
+mod concurrency_tests;
+mod utilization_tests;
+mod lod_tests;
+mod lanes_tests;
+mod lint_tests;
+mod common;
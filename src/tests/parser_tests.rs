@@ -1,9 +1,9 @@
 #[cfg(test)]
 mod test {
     use std::fs;
-    use std::fs::File;
+    use std::io::BufReader;
     use std::path::PathBuf;
-    use crate::parse::parse_ftr;
+    use crate::parse::{is_ftr_buffered, parse_ftr, read_from_reader, stream_from_reader};
 
     #[test]
     fn uncomp_parsing() {
@@ -104,4 +104,634 @@ mod test {
         println!();
     }
 
+    #[test]
+    fn from_reader() {
+        let bytes = fs::read("./example_files/my_db_c.ftr").unwrap();
+
+        // a byte slice can only be read, not seeked
+        let ftr = read_from_reader(&bytes[..]).unwrap();
+
+        let mut ftr_lazy = parse_ftr(PathBuf::from("./example_files/my_db_c.ftr")).unwrap();
+        for stream_id in ftr_lazy.tx_streams.keys().cloned().collect::<Vec<_>>() {
+            ftr_lazy.load_stream_into_memory(stream_id).unwrap();
+        }
+
+        assert_eq!(ftr.max_timestamp, ftr_lazy.max_timestamp);
+        assert_eq!(ftr.tx_relations.len(), ftr_lazy.tx_relations.len());
+        for (id, gen) in &ftr_lazy.tx_generators {
+            assert_eq!(ftr.get_generator(*id).unwrap().transactions.len(), gen.transactions.len());
+        }
+        assert!(ftr.tx_streams.values().all(|s| s.transactions_loaded));
+    }
+
+    #[test]
+    fn streaming() {
+        let bytes = fs::read("./example_files/my_db.ftr").unwrap();
+        let expected = read_from_reader(&bytes[..]).unwrap();
+
+        let (is_ftr, reader) = is_ftr_buffered(BufReader::new(&bytes[..]));
+        assert!(is_ftr);
+
+        let mut stream_reader = stream_from_reader(reader).unwrap();
+        let mut count = 0;
+        for tx in stream_reader.by_ref() {
+            let tx = tx.unwrap();
+            assert!(expected.get_generator(tx.get_gen_id()).unwrap().transactions.contains(&tx));
+            count += 1;
+        }
+
+        let ftr = stream_reader.into_ftr();
+        assert_eq!(count, expected.tx_generators.values().map(|g| g.transactions.len()).sum::<usize>());
+        assert!(ftr.tx_generators.values().all(|g| g.transactions.is_empty()));
+        assert_eq!(ftr.tx_streams.len(), expected.tx_streams.len());
+    }
+
+    #[test]
+    fn not_an_ftr() {
+        let (is_ftr, _) = is_ftr_buffered(&b"just some text"[..]);
+        assert!(!is_ftr);
+        assert!(!is_ftr_buffered(&[0xd9u8][..]).0);
+        assert!(read_from_reader(&b"just some text"[..]).is_err());
+    }
+
+    // hands out a single byte per read, like a slow pipe
+    struct Trickle<'a>(&'a [u8]);
+
+    impl std::io::Read for Trickle<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            let Some((first, rest)) = self.0.split_first() else { return Ok(0) };
+            if buf.is_empty() {
+                return Ok(0);
+            }
+            buf[0] = *first;
+            self.0 = rest;
+            Ok(1)
+        }
+    }
+
+    #[test]
+    fn short_reads() {
+        let bytes = fs::read("./example_files/my_db.ftr").unwrap();
+        let expected = read_from_reader(&bytes[..]).unwrap();
+
+        let (is_ftr, reader) = is_ftr_buffered(BufReader::with_capacity(1, Trickle(&bytes)));
+        assert!(is_ftr);
+        let ftr = read_from_reader(reader).unwrap();
+        assert_eq!(ftr.max_timestamp, expected.max_timestamp);
+        assert_eq!(ftr.tx_generators.len(), expected.tx_generators.len());
+    }
+
+    // writes the example file wrapped in a container to a temporary location and parses it
+    #[cfg(any(feature = "gzip", feature = "zstd", feature = "xz"))]
+    fn parse_wrapped(extension: &str, compress: impl Fn(&[u8]) -> Vec<u8>) -> crate::types::FTR {
+        let bytes = fs::read("./example_files/my_db_c.ftr").unwrap();
+        let path = std::env::temp_dir().join(format!("ftr_parser_test_{}.ftr.{}", std::process::id(), extension));
+        fs::write(&path, compress(&bytes)).unwrap();
+
+        let mut ftr = parse_ftr(path.clone()).unwrap();
+        fs::remove_file(path).unwrap();
+        for stream_id in ftr.tx_streams.keys().cloned().collect::<Vec<_>>() {
+            ftr.load_stream_into_memory(stream_id).unwrap();
+        }
+        ftr
+    }
+
+    #[cfg(any(feature = "gzip", feature = "zstd", feature = "xz"))]
+    fn assert_same_as_plain(ftr: &crate::types::FTR) {
+        let expected = read_from_reader(&fs::read("./example_files/my_db_c.ftr").unwrap()[..]).unwrap();
+        assert_eq!(ftr.tx_streams.len(), expected.tx_streams.len());
+        for (id, gen) in &expected.tx_generators {
+            assert_eq!(ftr.get_generator(*id).unwrap().transactions.len(), gen.transactions.len());
+        }
+    }
+
+    #[cfg(feature = "gzip")]
+    #[test]
+    fn gzip_parsing() {
+        use std::io::Write;
+        let ftr = parse_wrapped("gz", |bytes| {
+            let mut encoder = flate2::write::GzEncoder::new(vec![], flate2::Compression::default());
+            encoder.write_all(bytes).unwrap();
+            encoder.finish().unwrap()
+        });
+        assert_same_as_plain(&ftr);
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn zstd_parsing() {
+        let ftr = parse_wrapped("zst", |bytes| zstd::encode_all(bytes, 0).unwrap());
+        assert_same_as_plain(&ftr);
+    }
+
+    #[cfg(feature = "xz")]
+    #[test]
+    fn xz_parsing() {
+        use std::io::Write;
+        let ftr = parse_wrapped("xz", |bytes| {
+            let mut encoder = xz2::write::XzEncoder::new(vec![], 6);
+            encoder.write_all(bytes).unwrap();
+            encoder.finish().unwrap()
+        });
+        assert_same_as_plain(&ftr);
+    }
+
+    #[cfg(not(feature = "gzip"))]
+    #[test]
+    fn gzip_without_feature() {
+        let path = std::env::temp_dir().join(format!("ftr_parser_test_{}_nofeature.ftr.gz", std::process::id()));
+        fs::write(&path, [0x1f, 0x8b, 0x08, 0x00]).unwrap();
+
+        let err = parse_ftr(path.clone()).unwrap_err();
+        fs::remove_file(path).unwrap();
+        assert!(err.to_string().contains("`gzip` feature"));
+    }
+
+    #[test]
+    fn relations_linked() {
+        let bytes = fs::read("./example_files/my_db.ftr").unwrap();
+        let eager = crate::parse::read_from_bytes(bytes).unwrap();
+        let mut lazy = parse_ftr(PathBuf::from("./example_files/my_db.ftr")).unwrap();
+        for stream_id in lazy.tx_streams.keys().cloned().collect::<Vec<_>>() {
+            lazy.load_stream_into_memory(stream_id).unwrap();
+        }
+
+        for ftr in [&eager, &lazy] {
+            for gen in ftr.tx_generators.values() {
+                for tx in &gen.transactions {
+                    let out: Vec<_> = ftr.tx_relations.iter().filter(|r| r.source_tx_id == tx.get_tx_id()).collect();
+                    let inc: Vec<_> = ftr.tx_relations.iter().filter(|r| r.sink_tx_id == tx.get_tx_id() && r.source_tx_id != tx.get_tx_id()).collect();
+                    assert_eq!(tx.outgoing_relations(ftr).collect::<Vec<_>>(), out);
+                    assert_eq!(tx.incoming_relations(ftr).collect::<Vec<_>>(), inc);
+                }
+            }
+        }
+        assert!(eager.tx_generators.values().flat_map(|g| &g.transactions).any(|tx| !tx.inc_relations.is_empty()));
+
+        // relations are only stored once, including their names
+        let addr_phase: Vec<_> = eager.tx_relations.iter().filter(|r| &*r.name == "addr_phase").collect();
+        assert!(addr_phase.len() > 1);
+        assert!(addr_phase.windows(2).all(|w| std::sync::Arc::ptr_eq(&w[0].name, &w[1].name)));
+    }
+
+    #[test]
+    fn resolve_relation_streams() {
+        // my_db_c.ftr stores relations without their streams, my_db.ftr contains the same trace with streams
+        let mut expected = parse_ftr(PathBuf::from("./example_files/my_db.ftr")).unwrap();
+        expected.load_relations().unwrap();
+        let mut ftr = parse_ftr(PathBuf::from("./example_files/my_db_c.ftr")).unwrap();
+        ftr.load_relations().unwrap();
+        assert_eq!(ftr.unresolved_relations().count(), 36);
+
+        ftr.load_stream_into_memory(1).unwrap();
+        for rel in &ftr.tx_relations {
+            assert_eq!(rel.source_stream_id, Some(1));
+            assert_eq!(rel.sink_stream_id, None);
+        }
+
+        ftr.load_stream_into_memory(2).unwrap();
+        ftr.load_stream_into_memory(3).unwrap();
+        assert_eq!(ftr.unresolved_relations().count(), 0);
+        assert_eq!(ftr.tx_relations, expected.tx_relations);
+    }
+
+    #[test]
+    fn transaction_by_id() {
+        let mut expected = parse_ftr(PathBuf::from("./example_files/my_db.ftr")).unwrap();
+        for stream_id in expected.tx_streams.keys().cloned().collect::<Vec<_>>() {
+            expected.load_stream_into_memory(stream_id).unwrap();
+        }
+
+        let mut ftr = parse_ftr(PathBuf::from("./example_files/my_db.ftr")).unwrap();
+        assert!(ftr.find_transaction(41).is_none());
+
+        // only the block containing the transaction gets loaded
+        let tx = ftr.get_transaction(41).unwrap().unwrap().clone();
+        assert!(!ftr.get_stream(1).unwrap().transactions_loaded);
+        assert!(ftr.get_generator(4).unwrap().transactions.is_empty());
+        assert_eq!(&tx, expected.find_transaction(41).unwrap());
+        let location = ftr.locate_transaction(41).unwrap();
+        assert_eq!(location, expected.locate_transaction(41).unwrap());
+        assert_eq!(location.stream_id, 3);
+
+        assert!(ftr.get_transaction(100_000).unwrap().is_none());
+
+        // loading the whole stream afterward does not duplicate transactions
+        ftr.load_stream_into_memory(3).unwrap();
+        for gen_id in &ftr.get_stream(3).unwrap().generators {
+            assert_eq!(ftr.get_generator(*gen_id).unwrap().transactions, expected.get_generator(*gen_id).unwrap().transactions);
+        }
+        for gen in expected.tx_generators.values() {
+            for tx in &gen.transactions {
+                assert_eq!(ftr.get_transaction(tx.get_tx_id()).unwrap().unwrap(), tx);
+            }
+        }
+
+        ftr.drop_stream_from_memory(3);
+        assert!(ftr.find_transaction(41).is_none());
+        assert!(!ftr.get_stream(3).unwrap().transactions_loaded);
+    }
+
+    #[test]
+    fn transaction_by_id_in_small_blocks() {
+        use crate::tests::common::split_tx_blocks;
+
+        let bytes = split_tx_blocks(&fs::read("./example_files/my_db.ftr").unwrap(), 3);
+        let path = std::env::temp_dir().join(format!("ftr_parser_test_{}_blocks.ftr", std::process::id()));
+        fs::write(&path, bytes).unwrap();
+        let mut expected = parse_ftr(path.clone()).unwrap();
+        let mut ftr = parse_ftr(path.clone()).unwrap();
+        for stream_id in expected.tx_streams.keys().cloned().collect::<Vec<_>>() {
+            expected.load_stream_into_memory(stream_id).unwrap();
+        }
+        let num_blocks: usize = ftr.tx_streams.values().map(|stream| stream.tx_blocks.len()).sum();
+        assert!(ftr.get_stream(1).unwrap().tx_blocks.len() > 5);
+
+        // the search is bounded by the start times of the transactions around the id
+        let decoded = |ftr: &crate::types::FTR| ftr.tx_streams.values().flat_map(|stream| &stream.tx_blocks).filter(|b| b.tx_ids.is_some()).count();
+        assert!(ftr.get_transaction(28).unwrap().is_some());
+        assert!(decoded(&ftr) < num_blocks / 2);
+        // only a transaction that doesn't exist makes it decode all blocks
+        assert!(ftr.get_transaction(100_000).unwrap().is_none());
+        assert_eq!(decoded(&ftr), num_blocks);
+
+        // blocks loaded out of order end up in the order of the file
+        let mut tx_ids: Vec<usize> = expected.tx_index.keys().copied().collect();
+        tx_ids.sort();
+        for tx_id in tx_ids.iter().rev() {
+            assert_eq!(ftr.get_transaction(*tx_id).unwrap().unwrap(), expected.find_transaction(*tx_id).unwrap());
+        }
+        assert!(decoded(&ftr) <= num_blocks);
+        for (gen_id, gen) in &expected.tx_generators {
+            assert_eq!(ftr.get_generator(*gen_id).unwrap().transactions, gen.transactions);
+        }
+        for tx_id in &tx_ids {
+            assert_eq!(ftr.locate_transaction(*tx_id), expected.locate_transaction(*tx_id));
+        }
+        assert!(ftr.tx_streams.values().all(|stream| stream.transactions_loaded));
+        assert_eq!(ftr.lod_summary(1).unwrap().len(), expected.lod_summary(1).unwrap().len());
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn transaction_by_id_out_of_start_time_order() {
+        use crate::tests::common::rewrite_tx_blocks;
+
+        // transaction 37 begins after all others, as if its begin time had been set explicitly
+        let bytes = rewrite_tx_blocks(&fs::read("./example_files/my_db.ftr").unwrap(), 1, |tx_id, start, end| {
+            if tx_id == 37 { (3_300_000, 3_390_000) } else { (start, end) }
+        });
+        let path = std::env::temp_dir().join(format!("ftr_parser_test_{}_out_of_order.ftr", std::process::id()));
+        fs::write(&path, bytes).unwrap();
+        let mut ftr = parse_ftr(path.clone()).unwrap();
+
+        // the neighbours of the transaction bound its start time before the block with it is decoded
+        assert!(ftr.get_transaction(36).unwrap().is_some());
+        assert!(ftr.get_transaction(38).unwrap().is_some());
+        let tx = ftr.get_transaction(37).unwrap().unwrap();
+        assert_eq!(tx.event.start_time, 3_300_000u32.into());
+        assert_eq!(tx.get_u64("wr.addr").unwrap(), 191);
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn follow_relations() {
+        for file in ["./example_files/my_db.ftr", "./example_files/my_db_c.ftr"] {
+            let mut ftr = parse_ftr(PathBuf::from(file)).unwrap();
+
+            // write transaction 37 on the pipelined stream has its address phase on the address stream
+            let addr = ftr.follow_outgoing(37, Some("addr_phase")).unwrap();
+            assert_eq!(addr.len(), 1);
+            assert_eq!(addr[0].get_tx_id(), 38);
+            assert_eq!(addr[0].get_gen_id(), 6);
+            assert!(ftr.get_generator(7).unwrap().transactions.is_empty());
+
+            let phases: Vec<usize> = ftr.follow_outgoing(37, None).unwrap().iter().map(|tx| tx.get_tx_id()).collect();
+            assert_eq!(phases, vec![38, 41]);
+            assert!(ftr.follow_outgoing(37, Some("no_such_relation")).unwrap().is_empty());
+
+            let write = ftr.follow_incoming(41, Some("data_phase")).unwrap();
+            assert_eq!(write.len(), 1);
+            assert_eq!(write[0].get_tx_id(), 37);
+            assert!(ftr.follow_incoming(37, None).unwrap().is_empty());
+        }
+    }
+
+    #[test]
+    fn lazy_relations() {
+        for file in ["./example_files/my_db.ftr", "./example_files/my_db_c.ftr"] {
+            let eager = crate::parse::read_from_bytes(fs::read(file).unwrap()).unwrap();
+            assert!(eager.relations_loaded());
+
+            // relations are only decoded for the transactions that get loaded
+            let mut ftr = parse_ftr(PathBuf::from(file)).unwrap();
+            assert!(!ftr.relations_loaded());
+            assert!(ftr.tx_relations.is_empty());
+            ftr.load_stream_into_memory(2).unwrap();
+            assert!(!ftr.relations_loaded());
+            let expected: Vec<_> = eager.tx_relations.iter()
+                .filter(|rel| ftr.find_transaction(rel.source_tx_id).is_some() || ftr.find_transaction(rel.sink_tx_id).is_some())
+                .map(|rel| (rel.source_tx_id, rel.sink_tx_id))
+                .collect();
+            assert!(!expected.is_empty() && expected.len() < 36);
+            assert_eq!(ftr.tx_relations.iter().map(|rel| (rel.source_tx_id, rel.sink_tx_id)).collect::<Vec<_>>(), expected);
+
+            // loading further transactions brings in their relations, loading a stream again adds none twice
+            ftr.load_stream_into_memory(1).unwrap();
+            ftr.load_stream_into_memory(2).unwrap();
+            assert!(ftr.tx_relations.len() > expected.len());
+            for gen in ftr.tx_generators.values() {
+                for tx in &gen.transactions {
+                    assert_eq!(tx.outgoing_relations(&ftr).count(), eager.tx_relations.iter().filter(|r| r.source_tx_id == tx.get_tx_id()).count());
+                    assert_eq!(tx.incoming_relations(&ftr).count(), eager.tx_relations.iter().filter(|r| r.sink_tx_id == tx.get_tx_id()).count());
+                }
+            }
+
+            ftr.load_relations().unwrap();
+            assert!(ftr.relations_loaded());
+            let mut relations: Vec<_> = ftr.tx_relations.iter().map(|rel| (rel.source_tx_id, rel.sink_tx_id)).collect();
+            let mut expected: Vec<_> = eager.tx_relations.iter().map(|rel| (rel.source_tx_id, rel.sink_tx_id)).collect();
+            relations.sort();
+            expected.sort();
+            assert_eq!(relations, expected);
+
+            // following relations only loads the blocks of the transactions involved
+            let mut ftr = parse_ftr(PathBuf::from(file)).unwrap();
+            assert_eq!(ftr.follow_outgoing(37, None).unwrap().len(), 2);
+            assert!(!ftr.relations_loaded());
+            let expected = eager.tx_relations.iter()
+                .filter(|rel| ftr.find_transaction(rel.source_tx_id).is_some() || ftr.find_transaction(rel.sink_tx_id).is_some())
+                .count();
+            assert_eq!(ftr.tx_relations.len(), expected);
+        }
+    }
+
+    #[test]
+    fn time_range_queries() {
+        use num_bigint::BigUint;
+        use crate::types::{Transaction, TxSource};
+
+        let mut ftr = parse_ftr(PathBuf::from("./example_files/my_db.ftr")).unwrap();
+        assert!(ftr.transactions_at(1000u64).is_empty());
+        for stream_id in ftr.tx_streams.keys().cloned().collect::<Vec<_>>() {
+            ftr.load_stream_into_memory(stream_id).unwrap();
+        }
+
+        let brute_force = |ftr: &crate::types::FTR, gens: &[usize], start: u64, end: u64| {
+            let mut ids: Vec<usize> = gens.iter()
+                .flat_map(|id| &ftr.get_generator(*id).unwrap().transactions)
+                .filter(|tx| tx.get_start_time() <= BigUint::from(end) && tx.get_end_time() >= BigUint::from(start))
+                .map(|tx| tx.get_tx_id())
+                .collect();
+            ids.sort();
+            ids
+        };
+        let ids = |transactions: Vec<&Transaction>| {
+            let mut ids: Vec<usize> = transactions.iter().map(|tx| tx.get_tx_id()).collect();
+            ids.sort();
+            ids
+        };
+
+        for (start, end) in [(0, 0), (0, 3_400_000), (100_000, 100_000), (250_000, 700_000), (3_400_001, 4_000_000)] {
+            for stream_id in 1..=3 {
+                let gens = ftr.get_stream(stream_id).unwrap().generators.clone();
+                let transactions = ftr.transactions_in(TxSource::Stream(stream_id), start, end);
+                assert!(transactions.windows(2).all(|w| w[0].event.start_time <= w[1].event.start_time));
+                assert_eq!(ids(transactions), brute_force(&ftr, &gens, start, end));
+                for gen_id in gens {
+                    assert_eq!(ids(ftr.transactions_in(TxSource::Generator(gen_id), start, end)), brute_force(&ftr, &[gen_id], start, end));
+                }
+            }
+            assert_eq!(ids(ftr.transactions_at(start)), brute_force(&ftr, &[4, 5, 6, 7, 8], start, start));
+        }
+        let num_transactions = ftr.get_generator(4).unwrap().transactions.len() + ftr.get_generator(5).unwrap().transactions.len();
+        assert_eq!(ftr.transactions_in(TxSource::Stream(1), 0u64, 3_400_000u64).len(), num_transactions);
+        assert!(!ftr.transactions_at(250_000u64).is_empty());
+
+        // transactions that have been modified directly are found once the stream is indexed again
+        ftr.tx_generators.get_mut(&4).unwrap().transactions.truncate(1);
+        ftr.reindex_stream(1);
+        assert_eq!(ids(ftr.transactions_in(TxSource::Stream(1), 0u64, 3_400_000u64)), brute_force(&ftr, &[4, 5], 0, 3_400_000));
+        assert_eq!(ids(ftr.transactions_in(TxSource::Generator(4), 0u64, 3_400_000u64)).len(), 1);
+        assert_eq!(ftr.lod_summary(1).unwrap().len(), brute_force(&ftr, &[4, 5], 0, 3_400_000).len());
+        assert_eq!(ftr.tx_index.values().filter(|location| location.gen_id == 4).count(), 1);
+
+        ftr.drop_stream_from_memory(1);
+        assert!(ftr.transactions_in(TxSource::Stream(1), 0u64, 3_400_000u64).is_empty());
+    }
+
+    #[test]
+    fn navigation() {
+        let mut ftr = parse_ftr(PathBuf::from("./example_files/my_db.ftr")).unwrap();
+        for stream_id in ftr.tx_streams.keys().cloned().collect::<Vec<_>>() {
+            ftr.load_stream_into_memory(stream_id).unwrap();
+        }
+        let has_data = |tx: &crate::types::Transaction| tx.attributes.iter().any(|a| a.name == "data");
+
+        for stream_id in 1..=3 {
+            let stream = ftr.get_stream(stream_id).unwrap();
+            let transactions: Vec<_> = stream.generators.iter().flat_map(|id| &ftr.get_generator(*id).unwrap().transactions).collect();
+            for time in [0u64, 1, 150_000, 1_000_000, 3_399_999, 3_400_000] {
+                let time = num_bigint::BigUint::from(time);
+
+                let next = transactions.iter().filter(|tx| tx.event.start_time > time).map(|tx| tx.get_start_time()).min();
+                assert_eq!(stream.next_transaction(&ftr, time.clone()).map(|tx| tx.get_start_time()), next);
+                let previous = transactions.iter().filter(|tx| tx.event.end_time < time).map(|tx| tx.get_end_time()).max();
+                assert_eq!(stream.previous_transaction(&ftr, time.clone()).map(|tx| tx.get_end_time()), previous);
+
+                let next = transactions.iter().filter(|tx| tx.event.start_time > time && has_data(tx)).map(|tx| tx.get_start_time()).min();
+                let found = stream.next_transaction_matching(&ftr, time.clone(), has_data);
+                assert!(found.is_none_or(has_data));
+                assert_eq!(found.map(|tx| tx.get_start_time()), next);
+
+                for gen_id in &stream.generators {
+                    let gen = ftr.get_generator(*gen_id).unwrap();
+                    let previous = gen.transactions.iter().filter(|tx| tx.event.end_time < time).map(|tx| tx.get_end_time()).max();
+                    assert_eq!(gen.previous_transaction(&ftr, time.clone()).map(|tx| tx.get_end_time()), previous);
+                    let next = gen.transactions.iter().filter(|tx| tx.event.start_time > time).map(|tx| tx.get_start_time()).min();
+                    assert_eq!(gen.next_transaction(&ftr, time.clone()).map(|tx| tx.get_start_time()), next);
+                }
+            }
+        }
+        let stream = ftr.get_stream(1).unwrap();
+        assert!(stream.next_transaction(&ftr, 0u64).is_some());
+        assert!(stream.previous_transaction(&ftr, 3_400_000u64).is_some());
+        assert!(stream.next_transaction(&ftr, 3_400_000u64).is_none());
+        assert!(stream.previous_transaction(&ftr, 0u64).is_none());
+    }
+
+    #[test]
+    fn scope_tree() {
+        let ftr = parse_ftr(PathBuf::from("./example_files/my_db.ftr")).unwrap();
+        let scopes = ftr.scopes();
+
+        let names: Vec<&str> = scopes.root().children().map(|scope| scope.name.as_str()).collect();
+        assert_eq!(names, vec!["tr"]);
+        let tr = scopes.get("tr").unwrap();
+        assert_eq!(tr.stream_id, None);
+        let streams: Vec<&str> = tr.children().map(|scope| scope.path.as_str()).collect();
+        assert_eq!(streams, vec!["tr.addr_stream", "tr.data_stream", "tr.pipelined_stream"]);
+        let mut stream_ids = tr.stream_ids();
+        stream_ids.sort();
+        assert_eq!(stream_ids, vec![1, 2, 3]);
+
+        let pipelined = scopes.get("tr.pipelined_stream").unwrap();
+        assert_eq!(pipelined.stream_id, Some(1));
+        assert_eq!(pipelined.child("write").unwrap().generator_id, Some(5));
+        assert_eq!(scopes.get("tr.data_stream.wdata").unwrap().generator_id, Some(8));
+        assert!(scopes.get("tr.no_such_stream").is_none());
+        assert!(scopes.get("tr.data").is_none());
+
+        assert_eq!(scopes.stream_id("tr.data_stream"), Some(3));
+        assert_eq!(scopes.generator_id("tr.addr_stream.addr"), Some(6));
+        assert_eq!(ftr.get_stream_from_name("tr.addr_stream".to_string()).unwrap().id, 2);
+        assert!(ftr.get_stream_from_name("tr".to_string()).is_none());
+        assert_eq!(ftr.get_generator_from_name(Some(3), "rdata".to_string()).unwrap().id, 7);
+        assert!(ftr.get_generator_from_name(Some(3), "read".to_string()).is_none());
+
+        let paths = |pattern: &str| scopes.glob(pattern).unwrap().iter().map(|scope| scope.path.clone()).collect::<Vec<_>>();
+        assert_eq!(paths("tr.*_stream"), vec!["tr.addr_stream", "tr.data_stream", "tr.pipelined_stream"]);
+        assert_eq!(paths("tr.*.?data"), vec!["tr.data_stream.rdata", "tr.data_stream.wdata"]);
+        assert_eq!(paths("**.addr"), vec!["tr.addr_stream.addr"]);
+        assert_eq!(paths("**").len(), 1 + 3 + 5);
+        assert!(paths("*.addr").is_empty());
+    }
+
+    #[test]
+    fn transaction_hierarchy() {
+        use crate::hierarchy::HierarchyOptions;
+
+        let mut ftr = parse_ftr(PathBuf::from("./example_files/my_db.ftr")).unwrap();
+        // the children are in the order of the relations in the file
+        ftr.load_relations().unwrap();
+        for stream_id in ftr.tx_streams.keys().cloned().collect::<Vec<_>>() {
+            ftr.load_stream_into_memory(stream_id).unwrap();
+        }
+        let phases = HierarchyOptions::new().parent_of("addr_phase").parent_of("data_phase");
+        let hierarchy = ftr.hierarchy(&phases);
+
+        assert_eq!(hierarchy.children(37), &[38, 41]);
+        assert_eq!(hierarchy.parent(41), Some(37));
+        assert_eq!(hierarchy.parent(37), None);
+        assert!(hierarchy.roots().contains(&37));
+        assert!(!hierarchy.roots().contains(&38));
+        assert_eq!(hierarchy.depth_first(37).collect::<Vec<_>>(), vec![(37, 0), (38, 1), (41, 1)]);
+
+        // every loaded transaction is visited once, with the phases below the transactions of the pipelined stream
+        let walked: Vec<(usize, usize)> = hierarchy.walk().collect();
+        assert_eq!(walked.len(), ftr.tx_index.len());
+        for (tx_id, depth) in walked {
+            let stream_id = ftr.locate_transaction(tx_id).unwrap().stream_id;
+            assert_eq!(depth, if stream_id == 1 {0} else {1});
+            assert_eq!(hierarchy.ancestors(tx_id).count(), depth);
+        }
+
+        // relations in the other direction
+        let hierarchy = ftr.hierarchy(&HierarchyOptions::new().child_of("addr_phase"));
+        assert_eq!(hierarchy.parent(37), Some(38));
+        assert_eq!(hierarchy.children(38), &[37]);
+        assert!(ftr.hierarchy(&HierarchyOptions::lwtr()).children(37).is_empty());
+
+        // relations that would close a cycle are left out
+        let mut cycle = ftr.tx_relations[0].clone();
+        (cycle.source_tx_id, cycle.sink_tx_id) = (41, 37);
+        cycle.name = "data_phase".into();
+        ftr.tx_relations.push(cycle);
+        let hierarchy = ftr.hierarchy(&phases);
+        assert_eq!(hierarchy.parent(37), None);
+        assert!(hierarchy.children(41).is_empty());
+        assert_eq!(hierarchy.depth_first(37).count(), 3);
+
+        // a long chain whose last relation points back to its start, added in both orders
+        let chain_len = 100_000;
+        let mut rel = ftr.tx_relations[0].clone();
+        rel.name = "data_phase".into();
+        let chain: Vec<_> = (1000..1000 + chain_len).map(|tx_id| {
+            let mut rel = rel.clone();
+            (rel.source_tx_id, rel.sink_tx_id) = (tx_id, tx_id + 1);
+            rel
+        }).collect();
+        (rel.source_tx_id, rel.sink_tx_id) = (1000 + chain_len, 1000);
+        ftr.tx_relations.extend(chain.iter().cloned().chain([rel.clone()]));
+        let hierarchy = ftr.hierarchy(&phases);
+        assert_eq!(hierarchy.parent(1000), None);
+        assert_eq!(hierarchy.ancestors(1000 + chain_len).count(), chain_len);
+        ftr.tx_relations.truncate(ftr.tx_relations.len() - chain_len - 1);
+        ftr.tx_relations.extend([rel].into_iter().chain(chain.into_iter().rev()));
+        let hierarchy = ftr.hierarchy(&phases);
+        assert_eq!(hierarchy.parent(1000), Some(1000 + chain_len));
+        assert_eq!(hierarchy.parent(1000 + chain_len), Some(1000 + chain_len - 1));
+        assert_eq!(hierarchy.parent(1001), None);
+    }
+
+    #[test]
+    fn relation_chains() {
+        let mut ftr = parse_ftr(PathBuf::from("./example_files/my_db.ftr")).unwrap();
+        for stream_id in ftr.tx_streams.keys().cloned().collect::<Vec<_>>() {
+            ftr.load_stream_into_memory(stream_id).unwrap();
+        }
+        // extend the phases of transaction 37 to longer chains, with a cycle back to the start
+        for (source, sink) in [(38, 42), (41, 45), (42, 45), (45, 37)] {
+            let mut rel = ftr.tx_relations[0].clone();
+            (rel.source_tx_id, rel.sink_tx_id) = (source, sink);
+            rel.name = "next".into();
+            ftr.tx_relations.push(rel);
+        }
+        let graph = ftr.relation_graph(&[]);
+
+        let mut chains: Vec<Vec<usize>> = graph.chains_from(37).iter().map(|chain| chain.tx_ids()).collect();
+        chains.sort();
+        assert_eq!(chains, vec![vec![37, 38, 42, 45], vec![37, 41, 45]]);
+        // the relation from 45 back to 37 closes the cycles and is dropped
+        assert!(graph.successors(45).is_empty());
+        assert_eq!(graph.chains_from(45).iter().map(|chain| chain.tx_ids()).collect::<Vec<_>>(), vec![vec![45]]);
+
+        let chain = graph.longest_chain(37).unwrap();
+        assert_eq!(chain.tx_ids(), vec![37, 38, 42, 45]);
+        let tx = |id| ftr.find_transaction(id).unwrap();
+        assert_eq!(chain.latency(), num_bigint::BigInt::from(tx(45).get_end_time()) - num_bigint::BigInt::from(tx(37).get_start_time()));
+        assert_eq!(chain.hops[0].relation, None);
+        assert_eq!(ftr.tx_relations[chain.hops[1].relation.unwrap()].sink_tx_id, 38);
+        for hops in chain.hops.windows(2) {
+            assert_eq!(hops[1].gap, num_bigint::BigInt::from(hops[1].start.clone()) - num_bigint::BigInt::from(hops[0].end.clone()));
+        }
+        assert_eq!(chain.hops[2].duration(), tx(42).get_end_time() - tx(42).get_start_time());
+
+        // the critical path spends the most time in its transactions
+        let critical = graph.critical_path(37, 45).unwrap();
+        let busiest = graph.chains_from(37).iter().map(|chain| chain.busy_time()).max().unwrap();
+        assert_eq!(critical.busy_time(), busiest);
+        assert_eq!(*critical.tx_ids().last().unwrap(), 45);
+        assert_eq!(graph.critical_path(38, 45).unwrap().tx_ids(), vec![38, 42, 45]);
+        assert_eq!(graph.critical_path(41, 42), None);
+        assert_eq!(graph.critical_path(41, 2), None);
+        assert_eq!(graph.critical_path(41, 41).unwrap().tx_ids(), vec![41]);
+
+        // only the selected relations
+        let phases = ftr.relation_graph(&["addr_phase", "data_phase"]);
+        assert_eq!(phases.longest_chain(37).unwrap().tx_ids().len(), 2);
+        assert_eq!(phases.chains_from(37).len(), 2);
+        assert!(phases.longest_chain(100_000).is_none());
+
+        // a chain far longer than the stack could follow recursively, ending in a cycle
+        let gen = ftr.tx_generators.get_mut(&4).unwrap();
+        let mut tx = gen.transactions[0].clone();
+        let mut rel = ftr.tx_relations[0].clone();
+        rel.name = "long".into();
+        let tx_ids: Vec<usize> = (1_000_000..1_050_000).collect();
+        for tx_id in &tx_ids {
+            tx.event.tx_id = *tx_id;
+            ftr.tx_index.insert(*tx_id, crate::types::TxLocation { stream_id: 1, gen_id: 4, position: gen.transactions.len() });
+            gen.transactions.push(tx.clone());
+            (rel.source_tx_id, rel.sink_tx_id) = (*tx_id, tx_id + 1);
+            ftr.tx_relations.push(rel.clone());
+        }
+        ftr.tx_relations.last_mut().unwrap().sink_tx_id = tx_ids[0];
+        let long = ftr.relation_graph(&["long"]);
+        assert!(long.successors(*tx_ids.last().unwrap()).is_empty());
+        assert_eq!(long.chains_from(tx_ids[0]).len(), 1);
+        assert_eq!(long.longest_chain(tx_ids[0]).unwrap().tx_ids(), tx_ids);
+        assert_eq!(long.critical_path(tx_ids[0], *tx_ids.last().unwrap()).unwrap().tx_ids(), tx_ids);
+        assert_eq!(long.longest_chain(tx_ids[1]).unwrap().hops.len(), tx_ids.len() - 1);
+    }
 }
@@ -0,0 +1,92 @@
+#[cfg(test)]
+mod test {
+    use crate::query::{Query, MAX_NESTING};
+    use crate::tests::common::load_all;
+    use crate::types::{AttributeType, DataType, Transaction, FTR};
+
+    fn matching(ftr: &FTR, query: &str) -> Vec<usize> {
+        Query::parse(query).unwrap().filter(ftr).map(|tx| tx.get_tx_id()).collect()
+    }
+
+    fn expected(ftr: &FTR, predicate: impl Fn(&Transaction) -> bool) -> Vec<usize> {
+        let mut gens: Vec<_> = ftr.tx_generators.values().collect();
+        gens.sort_by_key(|gen| (gen.stream_id, gen.id));
+        gens.iter().flat_map(|gen| &gen.transactions).filter(|tx| predicate(tx)).map(|tx| tx.get_tx_id()).collect()
+    }
+
+    fn unsigned(tx: &Transaction, name: &str) -> Option<u64> {
+        tx.attributes.iter().find(|a| a.name == name).and_then(|a| match a.data_type {
+            DataType::Unsigned(u) => Some(u),
+            _ => None,
+        })
+    }
+
+    #[test]
+    fn queries() {
+        let ftr = load_all("./example_files/my_db.ftr");
+        let duration = |tx: &Transaction| tx.get_end_time() - tx.get_start_time();
+
+        let ids = matching(&ftr, r#"stream =~ "^tr\.(addr|data)_stream$" && addr >= 0x10"#);
+        assert!(!ids.is_empty());
+        assert_eq!(ids, expected(&ftr, |tx| ftr.get_generator(tx.get_gen_id()).unwrap().stream_id == 2 && unsigned(tx, "addr").is_some_and(|a| a >= 16)));
+
+        // the trace is in ns
+        let ids = matching(&ftr, "duration > 200us && kind == END");
+        assert!(!ids.is_empty());
+        assert_eq!(ids, expected(&ftr, |tx| duration(tx) > 200_000u64.into() && tx.attributes.iter().any(|a| matches!(a.kind, AttributeType::END))));
+        assert_eq!(matching(&ftr, "duration > 200000"), matching(&ftr, "duration > 0.2ms"));
+
+        let ids = matching(&ftr, "generator == write || (wr.data < 20 && !(id == 41))");
+        assert_eq!(ids, expected(&ftr, |tx| ftr.get_generator(tx.get_gen_id()).unwrap().name == "write"));
+        assert_eq!(matching(&ftr, "\"wr.addr\" == 191"), vec![37]);
+        assert_eq!(matching(&ftr, "start >= 2080000 && end <= 2360000 && generator != addr"), expected(&ftr, |tx| {
+            tx.get_start_time() >= 2_080_000u64.into() && tx.get_end_time() <= 2_360_000u64.into() && tx.get_gen_id() != 6
+        }));
+
+        // attributes only match values of their type
+        assert!(matching(&ftr, "addr == \"0\"").is_empty());
+        assert!(matching(&ftr, "no_such_attribute != 0").is_empty());
+        assert_eq!(matching(&ftr, "data_size =~ \"^24$\"").len(), 18);
+        assert_eq!(matching(&ftr, "kind != RECORD"), expected(&ftr, |tx| unsigned(tx, "data_size").is_none()));
+    }
+
+    #[test]
+    fn kind_scopes_attributes() {
+        let ftr = load_all("./example_files/my_db.ftr");
+        let tx = ftr.find_transaction(37).unwrap();
+        assert!(tx.attribute_by_kind("wr.addr", AttributeType::BEGIN).is_some());
+        assert!(tx.attributes.iter().any(|attr| attr.kind == AttributeType::RECORD));
+
+        assert_eq!(matching(&ftr, "kind == BEGIN && wr.addr == 191"), vec![37]);
+        assert_eq!(matching(&ftr, "wr.addr == 191 && (kind == BEGIN)"), vec![37]);
+        assert!(matching(&ftr, "kind == RECORD && wr.addr == 191").is_empty());
+        assert!(matching(&ftr, "kind != BEGIN && wr.addr == 191").is_empty());
+        assert_eq!(matching(&ftr, "kind != BEGIN && wr.addr == 191 || id == 37"), vec![37]);
+        assert_eq!(matching(&ftr, "kind == RECORD && data_size == 24").len(), 18);
+        assert!(matching(&ftr, "kind == BEGIN && kind == RECORD && data_size == 24").is_empty());
+        // without conditions on attributes, kind is checked on its own
+        assert_eq!(matching(&ftr, "kind == RECORD && id == 37"), vec![37]);
+    }
+
+    #[test]
+    fn invalid_queries() {
+        for query in ["", "addr >=", "addr 5", "(addr == 1", "addr == 1)", "stream == 5", "kind == OTHER", "start =~ \"1\"",
+            "addr == 10xs", "addr =~ \"(\"", "addr == \"open", "addr == 1 &&", "addr # 1"] {
+            assert!(Query::parse(query).is_err(), "{}", query);
+        }
+        let query: Query = "addr == 1 || data != 0b11".parse().unwrap();
+        assert_eq!(query.to_string(), "addr == 1 || data != 0b11");
+
+        let error = Query::parse("addr == 1 && data =~ \"(\"").unwrap_err().to_string();
+        assert!(error.starts_with("Invalid regular expression at position 21 of the query"), "{}", error);
+
+        // nesting is limited, long chains of conditions are not
+        let nested = |depth: usize| format!("{}addr == 1{}", "!(".repeat(depth), ")".repeat(depth));
+        assert!(Query::parse(&nested(MAX_NESTING / 2)).is_ok());
+        assert!(Query::parse(&nested(MAX_NESTING)).is_err());
+        assert!(Query::parse(&"!".repeat(100_000)).is_err());
+        let error = Query::parse(&"(".repeat(100_000)).unwrap_err().to_string();
+        assert_eq!(error, format!("More than {} nested parentheses or '!' at position {} of the query", MAX_NESTING, MAX_NESTING));
+        assert!(Query::parse(&vec!["addr == 1"; 100_000].join(" && ")).is_ok());
+    }
+}
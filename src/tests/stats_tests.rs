@@ -0,0 +1,107 @@
+#[cfg(test)]
+mod test {
+    use std::path::PathBuf;
+    use crate::parse::parse_ftr;
+    use crate::stats::{DurationStats, GroupBy, HistogramBucket, StatsOptions};
+    use crate::tests::common::{load_all, transactions_from_pairs};
+    use crate::types::{TxSource, FTR};
+
+    fn durations(ftr: &FTR, gen_ids: &[usize]) -> Vec<u64> {
+        let mut durations: Vec<u64> = gen_ids
+            .iter()
+            .flat_map(|gen_id| &ftr.tx_generators[gen_id].transactions)
+            .map(|tx| u64::try_from(&tx.event.end_time - &tx.event.start_time).unwrap())
+            .collect();
+        durations.sort();
+        durations
+    }
+
+    #[test]
+    fn duration_stats() {
+        let ftr = load_all("./example_files/my_db.ftr");
+        let options = StatsOptions::new().buckets(4);
+
+        for (source, gen_ids) in [(TxSource::Stream(1), vec![4, 5]), (TxSource::Generator(7), vec![7]), (TxSource::Stream(2), vec![6])] {
+            let expected = durations(&ftr, &gen_ids);
+            let stats = ftr.stats(source, &options).unwrap();
+            assert_eq!(stats.count, expected.len());
+            assert_eq!(stats.min, expected[0]);
+            assert_eq!(stats.max, *expected.last().unwrap());
+            let mean = expected.iter().sum::<u64>() as f64 / expected.len() as f64;
+            assert!((stats.mean - mean).abs() < 1e-6);
+            let variance = expected.iter().map(|d| (*d as f64 - mean).powi(2)).sum::<f64>() / expected.len() as f64;
+            assert!((stats.stddev - variance.sqrt()).abs() < 1e-6);
+            assert!(stats.min <= stats.p50 && stats.p50 <= stats.p90 && stats.p90 <= stats.p99 && stats.p99 <= stats.p999);
+            assert_eq!(stats.p999, stats.max);
+            assert!(expected.contains(&stats.p50));
+
+            assert_eq!(stats.histogram.len(), 4);
+            assert_eq!(stats.histogram.iter().map(|bucket| bucket.count).sum::<usize>(), expected.len());
+            assert_eq!(stats.histogram[0].start, stats.min);
+            assert!(stats.histogram.windows(2).all(|pair| pair[0].end == pair[1].start));
+            for bucket in &stats.histogram {
+                assert_eq!(bucket.count, expected.iter().filter(|d| bucket.start <= **d && **d < bucket.end).count());
+            }
+        }
+        assert!(ftr.stats(TxSource::Stream(42), &options).is_none());
+
+        let unloaded = parse_ftr(PathBuf::from("./example_files/my_db.ftr")).unwrap();
+        assert!(unloaded.stats(TxSource::Stream(1), &options).is_none());
+    }
+
+    #[test]
+    fn percentiles() {
+        let transactions = transactions_from_pairs(&(1..=12).map(|end| (0, end)).collect::<Vec<_>>());
+        let options = StatsOptions::new();
+        let stats = DurationStats::new(&transactions, &options).unwrap();
+        assert_eq!(stats.count, 12);
+        assert_eq!((stats.min, stats.max), (1, 12));
+        assert_eq!((stats.p50, stats.p90, stats.p99, stats.p999), (6, 11, 12, 12));
+        assert_eq!(stats.histogram.iter().map(|bucket| bucket.count).collect::<Vec<_>>(), vec![2, 1, 1, 1, 1, 2, 1, 1, 1, 1]);
+        assert!(DurationStats::new(&transactions[..0], &options).is_none());
+    }
+
+    #[test]
+    fn histogram_bucket_edges() {
+        let transactions = transactions_from_pairs(&[(0, 0), (0, 9), (0, 10), (0, 19), (0, 20), (0, 89), (0, 90), (0, 99)]);
+        let stats = DurationStats::new(&transactions, &StatsOptions::new()).unwrap();
+        let edges: Vec<(u64, u64)> = stats.histogram.iter().map(|bucket| (bucket.start, bucket.end)).collect();
+        assert_eq!(edges, (0..10).map(|i| (10 * i, 10 * i + 10)).collect::<Vec<_>>());
+        assert_eq!(stats.histogram.iter().map(|bucket| bucket.count).collect::<Vec<_>>(), vec![2, 2, 1, 0, 0, 0, 0, 0, 1, 2]);
+
+        // all durations in the first bucket if they are the same, and more buckets than durations
+        let transactions = transactions_from_pairs(&[(5, 12), (0, 7)]);
+        let stats = DurationStats::new(&transactions, &StatsOptions::new().buckets(4)).unwrap();
+        assert_eq!(stats.histogram[0], HistogramBucket { start: 7, end: 8, count: 2 });
+        assert!(stats.histogram[1..].iter().all(|bucket| bucket.count == 0 && bucket.start == 8 && bucket.end == 8));
+        let transactions = transactions_from_pairs(&[(0, 1), (0, 2)]);
+        let stats = DurationStats::new(&transactions, &StatsOptions::new().buckets(3)).unwrap();
+        assert_eq!(stats.histogram.iter().map(|bucket| (bucket.start, bucket.end, bucket.count)).collect::<Vec<_>>(), vec![(1, 2, 1), (2, 3, 1), (3, 3, 0)]);
+
+        let transactions = transactions_from_pairs(&[(0, 0), (0, u64::MAX)]);
+        let stats = DurationStats::new(&transactions, &StatsOptions::new().buckets(2)).unwrap();
+        assert_eq!(stats.histogram.iter().map(|bucket| bucket.count).collect::<Vec<_>>(), vec![1, 1]);
+        assert_eq!(stats.histogram[1].start, 1 << 63);
+    }
+
+    #[test]
+    fn grouped_stats() {
+        let ftr = load_all("./example_files/my_db.ftr");
+        let options = StatsOptions::new();
+
+        let by_generator = ftr.grouped_stats(TxSource::Stream(1), &GroupBy::Generator, &options);
+        assert_eq!(by_generator.keys().collect::<Vec<_>>(), vec!["read", "write"]);
+        assert_eq!(by_generator["read"], ftr.stats(TxSource::Generator(4), &options).unwrap());
+        assert_eq!(by_generator["write"], ftr.stats(TxSource::Generator(5), &options).unwrap());
+
+        let by_size = ftr.grouped_stats(TxSource::Stream(1), &GroupBy::Attribute("data_size".to_string()), &options);
+        assert_eq!(by_size.values().map(|stats| stats.count).sum::<usize>(), 18);
+        for (size, stats) in &by_size {
+            let count = ftr.tx_generators[&4].transactions.iter().chain(&ftr.tx_generators[&5].transactions)
+                .filter(|tx| tx.attribute("data_size").is_some_and(|attr| attr.value() == *size))
+                .count();
+            assert_eq!(stats.count, count);
+        }
+        assert!(ftr.grouped_stats(TxSource::Stream(1), &GroupBy::Attribute("no_such_attribute".to_string()), &options).is_empty());
+    }
+}
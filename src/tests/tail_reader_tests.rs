@@ -0,0 +1,64 @@
+#[cfg(test)]
+mod test {
+    use std::fs;
+    use std::fs::OpenOptions;
+    use std::io::Write;
+    use std::path::PathBuf;
+    use crate::parse::{parse_ftr, tail_ftr};
+
+    #[test]
+    fn growing_file() {
+        let bytes = fs::read("./example_files/my_db.ftr").unwrap();
+        let path = std::env::temp_dir().join(format!("ftr_parser_test_{}_tail.ftr", std::process::id()));
+        fs::write(&path, &bytes[..2]).unwrap();
+
+        let mut tail_reader = tail_ftr(path.clone()).unwrap();
+        assert_eq!(tail_reader.offset(), 0);
+        assert!(tail_reader.ftr().tx_streams.is_empty());
+
+        let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+        let mut written = 2;
+        let mut streams_seen = vec![];
+        let mut loaded = false;
+        while written < bytes.len() {
+            let next = (written + 97).min(bytes.len());
+            file.write_all(&bytes[written..next]).unwrap();
+            written = next;
+
+            let offset = tail_reader.offset();
+            let update = tail_reader.refresh().unwrap();
+            assert!(tail_reader.offset() >= offset);
+            assert!(tail_reader.offset() <= written as u64);
+            streams_seen.extend(update.new_streams);
+
+            // load a stream as soon as it exists, following blocks have to be loaded by refresh()
+            if !loaded && tail_reader.ftr().tx_streams.contains_key(&1) {
+                tail_reader.ftr_mut().load_stream_into_memory(1).unwrap();
+                loaded = true;
+            }
+            assert_eq!(update.finished, written == bytes.len());
+        }
+        assert!(tail_reader.refresh().unwrap().finished);
+        fs::remove_file(&path).unwrap();
+
+        let mut expected = parse_ftr(PathBuf::from("./example_files/my_db.ftr")).unwrap();
+        expected.load_stream_into_memory(1).unwrap();
+        let ftr = tail_reader.ftr();
+
+        streams_seen.sort();
+        let mut expected_streams: Vec<usize> = expected.tx_streams.keys().cloned().collect();
+        expected_streams.sort();
+        assert_eq!(streams_seen, expected_streams);
+        assert_eq!(ftr.max_timestamp, expected.max_timestamp);
+        assert_eq!(ftr.tx_relations.len(), expected.tx_relations.len());
+        for gen_id in &expected.get_stream(1).unwrap().generators {
+            let expected_gen = expected.get_generator(*gen_id).unwrap();
+            let gen = ftr.get_generator(*gen_id).unwrap();
+            assert_eq!(gen.transactions.len(), expected_gen.transactions.len());
+            for (tx, expected_tx) in gen.transactions.iter().zip(&expected_gen.transactions) {
+                assert_eq!(tx, expected_tx);
+                assert_eq!(tx.inc_relations.len() + tx.out_relations.len(), expected_tx.inc_relations.len() + expected_tx.out_relations.len());
+            }
+        }
+    }
+}
@@ -0,0 +1,47 @@
+#[cfg(test)]
+mod test {
+    use num_bigint::BigUint;
+    use crate::tests::common::{load_all, transactions_from_pairs};
+    use crate::types::TxSource;
+    use crate::utilization::{utilization, UtilizationOptions};
+
+    #[test]
+    fn utilization_windows() {
+        let ftr = load_all("./example_files/my_db.ftr");
+        let transactions: Vec<_> = ftr.tx_generators[&4].transactions.iter().chain(&ftr.tx_generators[&5].transactions).collect();
+
+        let windows = ftr.utilization(TxSource::Stream(1), &UtilizationOptions::new(200_000u64).sum_of("data_size")).unwrap();
+        assert_eq!(windows.len(), 17);
+        assert_eq!(windows[0].start, BigUint::default());
+        assert_eq!(windows.last().unwrap().end, BigUint::from(3_400_000u64));
+        assert!(windows.windows(2).all(|pair| pair[0].end == pair[1].start));
+        for window in &windows {
+            let starting: Vec<_> = transactions.iter().filter(|tx| window.start <= tx.event.start_time && tx.event.start_time < window.end).collect();
+            assert_eq!(window.count, starting.len());
+            assert_eq!(window.sum, starting.iter().map(|tx| tx.get_f64("data_size").unwrap()).sum::<f64>());
+            assert!((window.rate() - window.count as f64 / 200_000.0).abs() < 1e-12);
+            assert!((window.bandwidth() - window.sum / 200_000.0).abs() < 1e-12);
+            assert!((0.0..=1.0).contains(&window.busy_fraction()));
+        }
+        assert_eq!(windows.iter().map(|window| window.count).sum::<usize>(), transactions.len());
+
+        let busy: BigUint = ftr.concurrency(TxSource::Stream(1)).intervals_above(0).into_iter().map(|(start, end)| end - start).sum();
+        assert_eq!(windows.iter().map(|window| window.busy.clone()).sum::<BigUint>(), busy);
+
+        let without_sum = ftr.utilization(TxSource::Stream(1), &UtilizationOptions::new(200_000u64)).unwrap();
+        assert!(without_sum.iter().all(|window| window.sum == 0.0));
+
+        // a window of 1 would need millions of windows for this trace
+        assert!(ftr.utilization(TxSource::Stream(1), &UtilizationOptions::new(1u64)).is_err());
+        assert_eq!(ftr.utilization(TxSource::Stream(1), &UtilizationOptions::new(4u64)).unwrap().len(), 850_000);
+    }
+
+    #[test]
+    fn utilization_busy_time() {
+        let transactions = transactions_from_pairs(&[(5, 25), (10, 15), (40, 45)]);
+        let windows = utilization(&transactions, &BigUint::from(45u64), &UtilizationOptions::new(10u64)).unwrap();
+        let busy: Vec<f64> = windows.iter().map(|window| window.busy_fraction()).collect();
+        assert_eq!(busy, vec![0.5, 1.0, 0.5, 0.0, 0.5]);
+        assert_eq!(windows.iter().map(|window| window.count).collect::<Vec<_>>(), vec![1, 1, 0, 0, 1]);
+    }
+}
@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fmt::{Debug};
 use num_bigint::{BigInt, BigUint};
 use serde::{Deserialize, Serialize};
@@ -6,9 +6,21 @@ use crate::types::DataType::Error;
 use crate::types::Timescale::{Fs, Ms, Ns, Ps, S, Us};
 use core::fmt;
 use std::path::PathBuf;
+use std::sync::Arc;
+use color_eyre::eyre::eyre;
+use crate::attribute_tree::AttributeNode;
+use crate::concurrency::ConcurrencyProfile;
+use crate::de::{AttributeDeserializer, AttributeOptions};
 use crate::ftr_parser::FtrParser;
-
-type IsCompressed = bool;
+use crate::hierarchy::{Hierarchy, HierarchyOptions};
+use crate::interval_index::IntervalIndex;
+use crate::lanes::LaneAssignment;
+use crate::lint::Finding;
+use crate::lod::LodSummary;
+use crate::relation_graph::RelationGraph;
+use crate::scope::ScopeTree;
+use crate::stats::{DurationStats, GroupBy, StatsOptions};
+use crate::utilization::{UtilizationOptions, UtilizationWindow};
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct TxStream {
@@ -17,7 +29,86 @@ pub struct TxStream {
     pub kind: String,
     pub generators: Vec<usize>,
     pub transactions_loaded: bool,
-    pub(super) tx_block_ids: Vec<(u64, IsCompressed)>,
+    pub(super) tx_blocks: Vec<TxBlock>,
+}
+
+/// A transaction block of a stream in the FTR file.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub(crate) struct TxBlock {
+    /// File position of the block content
+    pub(crate) position: u64,
+    pub(crate) compressed: bool,
+    /// Start and end time of the block as written in the chunk
+    pub(crate) start_time: i64,
+    pub(crate) end_time: i64,
+    /// Smallest and largest transaction id in this block, known once the block has been decoded
+    pub(crate) tx_ids: Option<(usize, usize)>,
+    /// Earliest start and latest end time of the transactions in this block, known once the block has been decoded
+    pub(crate) tx_times: Option<(BigUint, BigUint)>,
+    /// Start times of the transactions with the smallest and the largest id, known once the block has been decoded
+    pub(crate) tx_id_starts: Option<(BigUint, BigUint)>,
+    /// Number of transactions of each generator in this block, known once the block has been decoded
+    pub(crate) gen_tx_counts: Vec<(usize, usize)>,
+    /// Whether the transactions of this block are in memory
+    pub(crate) loaded: bool,
+}
+
+impl TxBlock {
+    pub(crate) fn new(position: u64, compressed: bool, start_time: i64, end_time: i64) -> Self {
+        Self {
+            position,
+            compressed,
+            start_time,
+            end_time,
+            tx_ids: None,
+            tx_times: None,
+            tx_id_starts: None,
+            gen_tx_counts: vec![],
+            loaded: false,
+        }
+    }
+
+    // remembers what the block contains once it has been decoded, so that it does not need to be decoded again
+    // to know whether it contains a transaction
+    pub(crate) fn set_content(&mut self, transactions: &[Transaction]) {
+        let first = transactions.iter().min_by_key(|tx| tx.event.tx_id);
+        let last = transactions.iter().max_by_key(|tx| tx.event.tx_id);
+        self.tx_ids = first.zip(last).map(|(first, last)| (first.event.tx_id, last.event.tx_id));
+        self.tx_id_starts = first.zip(last).map(|(first, last)| (first.event.start_time.clone(), last.event.start_time.clone()));
+        let start = transactions.iter().map(|tx| &tx.event.start_time).min();
+        let end = transactions.iter().map(|tx| &tx.event.end_time).max();
+        self.tx_times = start.zip(end).map(|(start, end)| (start.clone(), end.clone()));
+
+        let mut gen_tx_counts: BTreeMap<usize, usize> = BTreeMap::new();
+        for tx in transactions {
+            *gen_tx_counts.entry(tx.event.gen_id).or_default() += 1;
+        }
+        self.gen_tx_counts = gen_tx_counts.into_iter().collect();
+    }
+}
+
+impl TxStream {
+    /// Returns the first loaded transaction of this stream that starts after `time`.
+    /// `ftr` has to be the FTR this stream belongs to.
+    pub fn next_transaction<'a>(&self, ftr: &'a FTR, time: impl Into<BigUint>) -> Option<&'a Transaction> {
+        ftr.next_transaction_in(TxSource::Stream(self.id), time.into(), |_| true)
+    }
+
+    /// Same as `next_transaction()`, but skips transactions that don't match the predicate.
+    pub fn next_transaction_matching<'a>(&self, ftr: &'a FTR, time: impl Into<BigUint>, predicate: impl Fn(&Transaction) -> bool) -> Option<&'a Transaction> {
+        ftr.next_transaction_in(TxSource::Stream(self.id), time.into(), predicate)
+    }
+
+    /// Returns the loaded transaction of this stream that ends last before `time`.
+    /// `ftr` has to be the FTR this stream belongs to.
+    pub fn previous_transaction<'a>(&self, ftr: &'a FTR, time: impl Into<BigUint>) -> Option<&'a Transaction> {
+        ftr.previous_transaction_in(TxSource::Stream(self.id), time.into(), |_| true)
+    }
+
+    /// Same as `previous_transaction()`, but skips transactions that don't match the predicate.
+    pub fn previous_transaction_matching<'a>(&self, ftr: &'a FTR, time: impl Into<BigUint>, predicate: impl Fn(&Transaction) -> bool) -> Option<&'a Transaction> {
+        ftr.previous_transaction_in(TxSource::Stream(self.id), time.into(), predicate)
+    }
 }
 
 impl PartialEq<Self> for TxStream {
@@ -34,6 +125,30 @@ pub struct TxGenerator {
     pub transactions: Vec<Transaction>,
 }
 
+impl TxGenerator {
+    /// Returns the first loaded transaction of this generator that starts after `time`.
+    /// `ftr` has to be the FTR this generator belongs to.
+    pub fn next_transaction<'a>(&self, ftr: &'a FTR, time: impl Into<BigUint>) -> Option<&'a Transaction> {
+        ftr.next_transaction_in(TxSource::Generator(self.id), time.into(), |_| true)
+    }
+
+    /// Same as `next_transaction()`, but skips transactions that don't match the predicate.
+    pub fn next_transaction_matching<'a>(&self, ftr: &'a FTR, time: impl Into<BigUint>, predicate: impl Fn(&Transaction) -> bool) -> Option<&'a Transaction> {
+        ftr.next_transaction_in(TxSource::Generator(self.id), time.into(), predicate)
+    }
+
+    /// Returns the loaded transaction of this generator that ends last before `time`.
+    /// `ftr` has to be the FTR this generator belongs to.
+    pub fn previous_transaction<'a>(&self, ftr: &'a FTR, time: impl Into<BigUint>) -> Option<&'a Transaction> {
+        ftr.previous_transaction_in(TxSource::Generator(self.id), time.into(), |_| true)
+    }
+
+    /// Same as `previous_transaction()`, but skips transactions that don't match the predicate.
+    pub fn previous_transaction_matching<'a>(&self, ftr: &'a FTR, time: impl Into<BigUint>, predicate: impl Fn(&Transaction) -> bool) -> Option<&'a Transaction> {
+        ftr.previous_transaction_in(TxSource::Generator(self.id), time.into(), predicate)
+    }
+}
+
 impl PartialEq<Self> for TxGenerator {
     fn eq(&self, other: &Self) -> bool {
         self.id == other.id &&
@@ -41,13 +156,53 @@ impl PartialEq<Self> for TxGenerator {
     }
 }
 
+/// Which relations of a file-backed FTR are held in `FTR::relations()`.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub(crate) enum RelationLoading {
+    /// Relations are added together with the transactions they come from or point to.
+    #[default]
+    LoadedStreams,
+    /// All relations are loaded.
+    All,
+}
+
+/// A relationship chunk of the file, which is decoded when transactions are loaded.
+#[derive(Debug, Clone)]
+pub(crate) struct RelationChunk {
+    /// Position of the content in the file
+    pub(crate) position: u64,
+    pub(crate) compressed: bool,
+    /// Smallest and largest transaction id of the relations in the chunk, known once it has been decoded.
+    /// Chunks without relations of the transactions that get loaded are not read again.
+    pub(crate) tx_ids: Option<(usize, usize)>,
+}
+
+/// The transactions of a stream or of a single generator.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TxSource {
+    Stream(usize),
+    Generator(usize),
+}
+
+/// Where a loaded transaction can be found.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TxLocation {
+    pub stream_id: usize,
+    pub gen_id: usize,
+    /// Position in `TxGenerator::transactions`
+    pub position: usize,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct TxRelation {
-    pub name: String,
+    /// Relations with the same name share the same string
+    pub name: Arc<str>,
     pub source_tx_id: usize,
     pub sink_tx_id: usize,
-    pub source_stream_id: usize,
-    pub sink_stream_id: usize
+    /// `None` until the source transaction has been loaded, if the file does not contain the stream of the relation
+    pub source_stream_id: Option<usize>,
+    /// `None` until the sink transaction has been loaded, if the file does not contain the stream of the relation
+    pub sink_stream_id: Option<usize>,
 }
 
 impl PartialEq<Self> for TxRelation {
@@ -59,12 +214,60 @@ impl PartialEq<Self> for TxRelation {
     }
 }
 
+/// Maps transaction ids to the positions of their relations in `FTR::relations()`.
+/// New relations are added to the index with `update()`, so it does not need to be rebuilt as relations get parsed.
+/// As relations are only ever appended, the positions that have been indexed stay valid.
+#[derive(Debug, Default, Clone)]
+pub(crate) struct RelationIndex {
+    indexed: usize,
+    by_source: HashMap<usize, Vec<usize>>,
+    by_sink: HashMap<usize, Vec<usize>>,
+}
+
+impl RelationIndex {
+    /// Indexes all relations that have been added since the last update.
+    pub(crate) fn update(&mut self, relations: &[TxRelation]) {
+        for (idx, rel) in relations.iter().enumerate().skip(self.indexed) {
+            self.by_source.entry(rel.source_tx_id).or_default().push(idx);
+            self.by_sink.entry(rel.sink_tx_id).or_default().push(idx);
+        }
+        self.indexed = relations.len();
+    }
+
+    pub(crate) fn outgoing(&self, tx_id: usize) -> &[usize] {
+        self.by_source.get(&tx_id).map_or(&[], |v| v.as_slice())
+    }
+
+    pub(crate) fn incoming(&self, tx_id: usize) -> &[usize] {
+        self.by_sink.get(&tx_id).map_or(&[], |v| v.as_slice())
+    }
+
+    /// Adds all indexed relations of the transaction to its incoming and outgoing relations
+    /// and fills in the stream of the transaction where the relations don't have one yet.
+    pub(crate) fn link(&self, relations: &mut [TxRelation], tx: &mut Transaction, stream_id: usize) {
+        let tx_id = tx.event.tx_id;
+        for idx in self.outgoing(tx_id) {
+            relations[*idx].source_stream_id.get_or_insert(stream_id);
+            tx.out_relations.push(*idx);
+        }
+        for idx in self.incoming(tx_id) {
+            relations[*idx].sink_stream_id.get_or_insert(stream_id);
+            // relations of a transaction to itself only count as outgoing
+            if relations[*idx].source_tx_id != tx_id {
+                tx.inc_relations.push(*idx);
+            }
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Transaction {
     pub event: Event,
     pub attributes: Vec<Attribute>,
-    pub inc_relations: Vec<TxRelation>,
-    pub out_relations: Vec<TxRelation>,
+    /// Positions of the incoming relations in `FTR::relations()`, see `incoming_relations()`
+    pub inc_relations: Vec<usize>,
+    /// Positions of the outgoing relations in `FTR::relations()`, see `outgoing_relations()`
+    pub out_relations: Vec<usize>,
 }
 
 impl PartialEq<Self> for Transaction {
@@ -90,6 +293,80 @@ impl Transaction {
     pub fn get_end_time(&self) -> BigUint {
         self.event.end_time.clone()
     }
+
+    /// Returns the first attribute with the given name.
+    ///
+    /// The attributes are searched one after the other, so this and the `get_*` methods take time linear in the
+    /// number of attributes. For lookups in many transactions, `FTR::attribute()` and its `get_*` methods are faster
+    /// as they know the position of each name in the transactions of a generator.
+    pub fn attribute(&self, name: &str) -> Option<&Attribute> {
+        self.attributes.iter().find(|attr| attr.name == name)
+    }
+
+    /// Returns the attribute with the given name and kind.
+    pub fn attribute_by_kind(&self, name: &str, kind: AttributeType) -> Option<&Attribute> {
+        self.attributes.iter().find(|attr| attr.name == name && attr.kind == kind)
+    }
+
+    /// Returns the value of the first attribute with the given name, which is searched like in `attribute()`.
+    pub fn get_u64(&self, name: &str) -> color_eyre::Result<u64> {
+        self.required_attribute(name)?.as_u64()
+    }
+
+    /// See `get_u64()`.
+    pub fn get_i64(&self, name: &str) -> color_eyre::Result<i64> {
+        self.required_attribute(name)?.as_i64()
+    }
+
+    /// See `get_u64()`.
+    pub fn get_f64(&self, name: &str) -> color_eyre::Result<f64> {
+        self.required_attribute(name)?.as_f64()
+    }
+
+    /// See `get_u64()`.
+    pub fn get_bool(&self, name: &str) -> color_eyre::Result<bool> {
+        self.required_attribute(name)?.as_bool()
+    }
+
+    /// See `get_u64()`.
+    pub fn get_str(&self, name: &str) -> color_eyre::Result<&str> {
+        self.required_attribute(name)?.as_str()
+    }
+
+    /// See `get_u64()`.
+    pub fn get_time(&self, name: &str) -> color_eyre::Result<u64> {
+        self.required_attribute(name)?.as_time()
+    }
+
+    /// Arranges the attributes by their names, see `AttributeNode`.
+    pub fn attribute_tree(&self) -> AttributeNode<'_> {
+        AttributeNode::new(&self.attributes)
+    }
+
+    /// Deserializes the attributes of this transaction into a struct whose fields are named like the attributes.
+    pub fn deserialize_attributes<'a, T: Deserialize<'a>>(&'a self) -> color_eyre::Result<T> {
+        self.deserialize_attributes_with(&AttributeOptions::new())
+    }
+
+    /// Same as `deserialize_attributes()`, with options to select attributes by their kind and to nest dotted names.
+    pub fn deserialize_attributes_with<'a, T: Deserialize<'a>>(&'a self, options: &AttributeOptions) -> color_eyre::Result<T> {
+        T::deserialize(AttributeDeserializer::new(self, options))
+            .map_err(|e| eyre!("Cannot deserialize the attributes of transaction {}: {}", self.event.tx_id, e))
+    }
+
+    fn required_attribute(&self, name: &str) -> color_eyre::Result<&Attribute> {
+        self.attribute(name).ok_or_else(|| eyre!("Transaction {} has no attribute '{}'", self.event.tx_id, name))
+    }
+
+    /// Relations that point to this transaction. `ftr` has to be the FTR this transaction was loaded from.
+    pub fn incoming_relations<'a>(&'a self, ftr: &'a FTR) -> impl Iterator<Item = &'a TxRelation> + 'a {
+        self.inc_relations.iter().filter_map(|idx| ftr.tx_relations.get(*idx))
+    }
+
+    /// Relations that start at this transaction. `ftr` has to be the FTR this transaction was loaded from.
+    pub fn outgoing_relations<'a>(&'a self, ftr: &'a FTR) -> impl Iterator<Item = &'a TxRelation> + 'a {
+        self.out_relations.iter().filter_map(|idx| ftr.tx_relations.get(*idx))
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -100,6 +377,12 @@ pub struct Event {
     pub end_time: BigUint,
 }
 
+impl Default for Event {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Event {
     pub fn new() -> Self{
         let tx_id = 0;
@@ -158,6 +441,63 @@ impl Attribute {
         }
     }
 
+    /// Returns the value of unsigned, pointer and time attributes, of non-negative integers
+    /// and of bit vectors with up to 64 bits.
+    pub fn as_u64(&self) -> color_eyre::Result<u64> {
+        match &self.data_type {
+            DataType::Unsigned(u) | DataType::Pointer(u) | DataType::Time(u) => Ok(*u),
+            DataType::Integer(i) => u64::try_from(*i).map_err(|_| self.conversion_error("u64")),
+            DataType::BitVector(s) => u64::from_str_radix(s, 2).map_err(|_| self.conversion_error("u64")),
+            _ => Err(self.conversion_error("u64")),
+        }
+    }
+
+    /// Returns the value of integer attributes and of unsigned, pointer and time attributes that fit into an i64.
+    pub fn as_i64(&self) -> color_eyre::Result<i64> {
+        match &self.data_type {
+            DataType::Integer(i) => Ok(*i),
+            DataType::Unsigned(u) | DataType::Pointer(u) | DataType::Time(u) => i64::try_from(*u).map_err(|_| self.conversion_error("i64")),
+            _ => Err(self.conversion_error("i64")),
+        }
+    }
+
+    /// Returns the value of floating and fixed point attributes, integers are converted.
+    pub fn as_f64(&self) -> color_eyre::Result<f64> {
+        match &self.data_type {
+            DataType::FloatingPointNumber(f) | DataType::FixedPointInteger(f) | DataType::UnsignedFixedPointInteger(f) => Ok(*f as f64),
+            DataType::Integer(i) => Ok(*i as f64),
+            DataType::Unsigned(u) | DataType::Pointer(u) | DataType::Time(u) => Ok(*u as f64),
+            _ => Err(self.conversion_error("f64")),
+        }
+    }
+
+    pub fn as_bool(&self) -> color_eyre::Result<bool> {
+        match &self.data_type {
+            DataType::Boolean(b) => Ok(*b),
+            _ => Err(self.conversion_error("bool")),
+        }
+    }
+
+    /// Returns the value of string, enumeration and vector attributes.
+    pub fn as_str(&self) -> color_eyre::Result<&str> {
+        match &self.data_type {
+            DataType::String(s) | DataType::Enumeration(s) | DataType::BitVector(s) | DataType::LogicVector(s) => Ok(s),
+            _ => Err(self.conversion_error("str")),
+        }
+    }
+
+    /// Returns the value of time attributes, in the time scale of the trace.
+    pub fn as_time(&self) -> color_eyre::Result<u64> {
+        match &self.data_type {
+            DataType::Time(t) => Ok(*t),
+            _ => Err(self.conversion_error("time")),
+        }
+    }
+
+    fn conversion_error(&self, target: &str) -> color_eyre::Report {
+        eyre!("Attribute '{}' with value {:?} cannot be converted to {}", self.name, self.data_type, target)
+    }
+
     pub fn value(&self) -> String {
         match &self.data_type {
             DataType::Boolean(b) => b.to_string(),
@@ -194,7 +534,7 @@ pub enum DataType {
     Error,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum AttributeType {
     BEGIN,
     RECORD,
@@ -209,11 +549,73 @@ pub struct FTR {
     pub str_dict: HashMap<usize, String>,
     pub tx_streams: HashMap<usize, TxStream>,
     pub tx_generators: HashMap<usize, TxGenerator>,
-    pub tx_relations: Vec<TxRelation>,
+    // relations between transactions, which are only ever appended, see `relations()`
+    pub(crate) tx_relations: Vec<TxRelation>,
     pub(crate) path: Option<PathBuf>,
+    #[serde(skip)]
+    pub(crate) relation_chunks: Vec<RelationChunk>,
+    #[serde(skip)]
+    pub(crate) relation_loading: RelationLoading,
+    // relations in `tx_relations` by relationship chunk and position in the chunk, so none is added twice
+    #[serde(skip)]
+    pub(crate) added_relations: HashSet<(usize, usize)>,
+    #[serde(skip)]
+    pub(crate) relation_index: RelationIndex,
+    // locations of all loaded transactions by their id
+    #[serde(skip)]
+    pub(crate) tx_index: HashMap<usize, TxLocation>,
+    // loaded transactions of every stream and generator by their time
+    #[serde(skip)]
+    pub(crate) interval_indices: HashMap<TxSource, IntervalIndex>,
+    // summaries of fully loaded streams for zoomed-out views
+    #[serde(skip)]
+    pub(crate) lod_summaries: HashMap<usize, LodSummary>,
+    // design hierarchy of the streams and generators, which also indexes them by name
+    #[serde(skip)]
+    pub(crate) scopes: ScopeTree,
+    // interned relation names by their dictionary id
+    #[serde(skip)]
+    pub(crate) relation_names: HashMap<usize, Arc<str>>,
+    // position of every attribute name in the loaded transactions of each generator, see `attribute()`
+    #[serde(skip)]
+    pub(crate) attribute_slots: HashMap<usize, HashMap<String, usize>>,
+    // ids of the dictionary entries that the parsed parts of the file refer to, see `lint()`
+    #[serde(skip)]
+    pub(crate) used_dict_ids: HashSet<usize>,
+    // decompressed copy of a compressed input file, which `path` points to. Deleted on drop.
+    #[cfg(any(feature = "gzip", feature = "zstd", feature = "xz"))]
+    #[serde(skip)]
+    pub(crate) temp_file: Option<tempfile::TempPath>,
 }
 
 impl FTR {
+    pub(crate) fn new(path: Option<PathBuf>) -> Self {
+        // without a file to read them from later, relations are read together with everything else
+        let relation_loading = if path.is_none() { RelationLoading::All } else { RelationLoading::default() };
+        FTR{
+            time_scale: Timescale::None,
+            str_dict: HashMap::new(),
+            tx_streams: HashMap::new(),
+            max_timestamp: BigInt::from(0),
+            tx_generators: HashMap::new(),
+            tx_relations: vec![],
+            path,
+            relation_chunks: vec![],
+            relation_loading,
+            added_relations: HashSet::new(),
+            relation_index: RelationIndex::default(),
+            relation_names: HashMap::new(),
+            attribute_slots: HashMap::new(),
+            used_dict_ids: HashSet::new(),
+            tx_index: HashMap::new(),
+            interval_indices: HashMap::new(),
+            lod_summaries: HashMap::new(),
+            scopes: ScopeTree::default(),
+            #[cfg(any(feature = "gzip", feature = "zstd", feature = "xz"))]
+            temp_file: None,
+        }
+    }
+
     // Takes a stream id and loads all associated transactions into memory
     pub fn load_stream_into_memory(&mut self, stream_id: usize) -> color_eyre::Result<()>{
         let mut ftr_parser = FtrParser::new(self);
@@ -223,11 +625,177 @@ impl FTR {
         Ok(())
     }
 
+    /// Loads all relations of the file, see `relations()`.
+    /// Otherwise only the relations from or to transactions that have been loaded are there.
+    pub fn load_relations(&mut self) -> color_eyre::Result<()>{
+        FtrParser::new(self).load_relations()
+    }
+
+    /// Returns true if `relations()` holds all relations of the trace.
+    pub fn relations_loaded(&self) -> bool {
+        self.relation_loading == RelationLoading::All
+    }
+
     // drops all transactions from this stream from memory, but the stream itself doesn't get deleted
     pub fn drop_stream_from_memory(&mut self, stream_id: usize) {
-        for gen_id in &self.tx_streams.get(&stream_id).expect("").generators {
-            self.tx_generators.get_mut(gen_id).unwrap().transactions = vec![];
+        let stream = self.tx_streams.get_mut(&stream_id).expect("");
+        for gen_id in &stream.generators {
+            for tx in std::mem::take(&mut self.tx_generators.get_mut(gen_id).unwrap().transactions) {
+                self.tx_index.remove(&tx.event.tx_id);
+            }
+            self.interval_indices.remove(&TxSource::Generator(*gen_id));
+        }
+        self.interval_indices.remove(&TxSource::Stream(stream_id));
+        self.lod_summaries.remove(&stream_id);
+        for tx_block in stream.tx_blocks.iter_mut() {
+            tx_block.loaded = false;
         }
+        stream.transactions_loaded = false;
+    }
+
+    /// Returns the transaction with id `tx_id`, if it is loaded.
+    pub fn find_transaction(&self, tx_id: usize) -> Option<&Transaction> {
+        let location = self.locate_transaction(tx_id)?;
+        self.tx_generators.get(&location.gen_id)?.transactions.get(location.position)
+    }
+
+    /// Returns where the transaction with id `tx_id` can be found, if it is loaded.
+    pub fn locate_transaction(&self, tx_id: usize) -> Option<TxLocation> {
+        let location = self.tx_index.get(&tx_id)?;
+        let tx = self.tx_generators.get(&location.gen_id)?.transactions.get(location.position);
+        if tx.is_some_and(|tx| tx.event.tx_id == tx_id) {
+            return Some(*location);
+        }
+        // the transactions have been modified from outside, fall back to searching them
+        self.tx_generators.values().find_map(|gen| {
+            gen.transactions
+                .iter()
+                .position(|tx| tx.event.tx_id == tx_id)
+                .map(|position| TxLocation{stream_id: gen.stream_id, gen_id: gen.id, position})
+        })
+    }
+
+    /// Returns the transaction with id `tx_id`.
+    /// If it is not loaded yet, only the transaction block containing it gets loaded into memory.
+    pub fn get_transaction(&mut self, tx_id: usize) -> color_eyre::Result<Option<&Transaction>> {
+        if self.locate_transaction(tx_id).is_none() {
+            FtrParser::new(self).load_block_of_transaction(tx_id, None)?;
+        }
+        Ok(self.find_transaction(tx_id))
+    }
+
+    /// Returns the loaded transactions of a stream or generator that overlap with the time range from `start`
+    /// to `end`, both included, ordered by their start time.
+    pub fn transactions_in(&self, source: TxSource, start: impl Into<BigUint>, end: impl Into<BigUint>) -> Vec<&Transaction> {
+        let (start, end) = (start.into(), end.into());
+        match self.interval_index(source) {
+            Some(index) => index
+                .query(&start, &end)
+                .filter_map(|(gen_id, position)| self.tx_generators.get(&gen_id)?.transactions.get(position))
+                .collect(),
+            None => {
+                let mut transactions: Vec<&Transaction> = self.source_transactions(source)
+                    .filter(|tx| tx.event.start_time <= end && tx.event.end_time >= start)
+                    .collect();
+                transactions.sort_by(|a, b| a.event.start_time.cmp(&b.event.start_time));
+                transactions
+            }
+        }
+    }
+
+    // the first loaded transaction that starts after `time` and matches the predicate
+    fn next_transaction_in(&self, source: TxSource, time: BigUint, predicate: impl Fn(&Transaction) -> bool) -> Option<&Transaction> {
+        match self.interval_index(source) {
+            Some(index) => index
+                .starting_after(&time)
+                .filter_map(|(gen_id, position)| self.tx_generators.get(&gen_id)?.transactions.get(position))
+                .find(|tx| predicate(tx)),
+            None => self.source_transactions(source)
+                .filter(|tx| tx.event.start_time > time && predicate(tx))
+                .min_by(|a, b| a.event.start_time.cmp(&b.event.start_time)),
+        }
+    }
+
+    // the last loaded transaction that ends before `time` and matches the predicate
+    fn previous_transaction_in(&self, source: TxSource, time: BigUint, predicate: impl Fn(&Transaction) -> bool) -> Option<&Transaction> {
+        match self.interval_index(source) {
+            Some(index) => index
+                .ending_before(&time)
+                .filter_map(|(gen_id, position)| self.tx_generators.get(&gen_id)?.transactions.get(position))
+                .find(|tx| predicate(tx)),
+            None => self.source_transactions(source)
+                .filter(|tx| tx.event.end_time < time && predicate(tx))
+                .max_by(|a, b| a.event.end_time.cmp(&b.event.end_time)),
+        }
+    }
+
+    fn interval_index(&self, source: TxSource) -> Option<&IntervalIndex> {
+        self.interval_indices.get(&source)
+    }
+
+    pub(crate) fn source_transactions(&self, source: TxSource) -> impl Iterator<Item = &Transaction> {
+        let generators: Vec<&TxGenerator> = match source {
+            TxSource::Stream(stream_id) => self.tx_streams
+                .get(&stream_id)
+                .map_or(vec![], |stream| stream.generators.iter().filter_map(|id| self.tx_generators.get(id)).collect()),
+            TxSource::Generator(gen_id) => self.tx_generators.get(&gen_id).into_iter().collect(),
+        };
+        generators.into_iter().flat_map(|gen| &gen.transactions)
+    }
+
+    /// Returns all loaded transactions that are active at `time`, grouped by stream and ordered by their start time.
+    pub fn transactions_at(&self, time: impl Into<BigUint>) -> Vec<&Transaction> {
+        let time = time.into();
+        let mut stream_ids: Vec<usize> = self.tx_streams.keys().cloned().collect();
+        stream_ids.sort();
+        stream_ids
+            .into_iter()
+            .flat_map(|stream_id| self.transactions_in(TxSource::Stream(stream_id), time.clone(), time.clone()))
+            .collect()
+    }
+
+    /// Follows the outgoing relations of the transaction with id `tx_id` and returns the transactions they point to.
+    /// If `relation_name` is given, only relations with this name are followed.
+    /// The transaction and its targets get loaded if they are not loaded yet, but only the transaction blocks that contain them.
+    pub fn follow_outgoing(&mut self, tx_id: usize, relation_name: Option<&str>) -> color_eyre::Result<Vec<&Transaction>> {
+        // the relations of a transaction are loaded together with it
+        FtrParser::new(self).load_block_of_transaction(tx_id, None)?;
+        self.relation_index.update(&self.tx_relations);
+        let targets: Vec<(usize, Option<usize>)> = self.relation_index
+            .outgoing(tx_id)
+            .iter()
+            .map(|idx| &self.tx_relations[*idx])
+            .filter(|rel| relation_name.is_none_or(|name| *rel.name == *name))
+            .map(|rel| (rel.sink_tx_id, rel.sink_stream_id))
+            .collect();
+        self.follow(targets)
+    }
+
+    /// Follows the incoming relations of the transaction with id `tx_id` and returns the transactions they come from.
+    /// If `relation_name` is given, only relations with this name are followed.
+    /// The transaction and its sources get loaded if they are not loaded yet, but only the transaction blocks that contain them.
+    pub fn follow_incoming(&mut self, tx_id: usize, relation_name: Option<&str>) -> color_eyre::Result<Vec<&Transaction>> {
+        // the relations of a transaction are loaded together with it
+        FtrParser::new(self).load_block_of_transaction(tx_id, None)?;
+        self.relation_index.update(&self.tx_relations);
+        let targets: Vec<(usize, Option<usize>)> = self.relation_index
+            .incoming(tx_id)
+            .iter()
+            .map(|idx| &self.tx_relations[*idx])
+            .filter(|rel| relation_name.is_none_or(|name| *rel.name == *name))
+            .map(|rel| (rel.source_tx_id, rel.source_stream_id))
+            .collect();
+        self.follow(targets)
+    }
+
+    // loads the given transactions, if necessary, and returns the ones that exist
+    fn follow(&mut self, targets: Vec<(usize, Option<usize>)>) -> color_eyre::Result<Vec<&Transaction>> {
+        for (tx_id, stream_id) in &targets {
+            if self.locate_transaction(*tx_id).is_none() {
+                FtrParser::new(self).load_block_of_transaction(*tx_id, *stream_id)?;
+            }
+        }
+        Ok(targets.iter().filter_map(|(tx_id, _)| self.find_transaction(*tx_id)).collect())
     }
 
     pub fn get_stream(&self, stream_id: usize) -> Option<&TxStream> {
@@ -235,21 +803,205 @@ impl FTR {
     }
 
     pub fn get_stream_from_name(&self, name: String) -> Option<&TxStream> {
-        self.tx_streams
-            .values()
-            .find(|t| t.name == name)
+        match self.scopes.stream_id(&name).and_then(|id| self.tx_streams.get(&id)) {
+            Some(stream) if stream.name == name => Some(stream),
+            // streams that have not been added to the scope tree, e.g. after deserializing the FTR
+            _ => self.tx_streams.values().find(|t| t.name == name),
+        }
+    }
+
+    /// Arranges the transactions as a tree, with the relations named in `options` as parent/child relations.
+    /// For FTRs backed by a file, the relations have to be loaded first, see `load_relations()`.
+    pub fn hierarchy(&self, options: &HierarchyOptions) -> Hierarchy {
+        Hierarchy::new(self, options)
+    }
+
+    /// Builds the graph of the relations between loaded transactions, with the relations named in `relation_names`,
+    /// or all relations if it is empty.
+    pub fn relation_graph(&self, relation_names: &[&str]) -> RelationGraph<'_> {
+        RelationGraph::new(self, relation_names)
+    }
+
+    /// Returns the design hierarchy of the streams and generators.
+    pub fn scopes(&self) -> &ScopeTree {
+        &self.scopes
+    }
+
+    /// Returns the duration statistics of the loaded transactions of a stream or generator,
+    /// or `None` if none of them is loaded.
+    pub fn stats(&self, source: TxSource, options: &StatsOptions) -> Option<DurationStats> {
+        DurationStats::new(self.source_transactions(source), options)
+    }
+
+    /// Returns the duration statistics of the loaded transactions of a stream or generator for every group of transactions.
+    pub fn grouped_stats(&self, source: TxSource, group_by: &GroupBy, options: &StatsOptions) -> BTreeMap<String, DurationStats> {
+        DurationStats::grouped(self, source, group_by, options)
+    }
+
+    /// Returns the number of loaded transactions of a stream or generator that are in flight over time.
+    pub fn concurrency(&self, source: TxSource) -> ConcurrencyProfile {
+        ConcurrencyProfile::new(self.source_transactions(source))
+    }
+
+    /// Divides `[0, max_timestamp]` into windows of equal length and returns how busy a stream or generator is
+    /// in each of them, based on its loaded transactions.
+    /// Fails if that would be more than `utilization::MAX_WINDOWS` windows.
+    pub fn utilization(&self, source: TxSource, options: &UtilizationOptions) -> color_eyre::Result<Vec<UtilizationWindow>> {
+        let max_timestamp = self.max_timestamp.to_biguint().unwrap_or_default();
+        crate::utilization::utilization(self.source_transactions(source), &max_timestamp, options)
+    }
+
+    /// Returns the level-of-detail summary of a stream, which is built when all its transactions have been loaded.
+    /// Returns `None` if the stream is not loaded.
+    pub fn lod_summary(&self, stream_id: usize) -> Option<&LodSummary> {
+        self.lod_summaries.get(&stream_id)
+    }
+
+    /// Assigns the loaded transactions of a stream or generator to lanes, so that overlapping transactions
+    /// can be drawn in separate rows.
+    pub fn lanes(&self, source: TxSource) -> LaneAssignment {
+        LaneAssignment::new(self.source_transactions(source))
+    }
+
+    /// Checks the trace for broken or suspicious content and returns what was found.
+    /// Only loaded transactions are checked, and transaction blocks against the transactions decoded from them.
+    /// Relations and dictionary entries are only checked once all streams and relations are loaded.
+    /// Checks that are left out because of this are reported as a `Check::Skipped` finding.
+    pub fn lint(&self) -> Vec<Finding> {
+        crate::lint::lint(self)
+    }
+
+    /// Updates the indices of a stream after its transactions in `TxGenerator::transactions` have been modified
+    /// directly. Until then, lookups by id, time range queries and the level-of-detail summary of the stream
+    /// still see the transactions as they were loaded.
+    pub fn reindex_stream(&mut self, stream_id: usize) {
+        let Some(stream) = self.tx_streams.get(&stream_id) else { return };
+        self.tx_index.retain(|_, location| location.stream_id != stream_id);
+        for gen in stream.generators.iter().filter_map(|gen_id| self.tx_generators.get(gen_id)) {
+            for (position, tx) in gen.transactions.iter().enumerate() {
+                self.tx_index.insert(tx.event.tx_id, TxLocation { stream_id, gen_id: gen.id, position });
+            }
+        }
+        self.index_stream(stream_id);
+    }
+
+    // builds the interval indices of a stream and its generators once new transactions have been stored,
+    // and the level-of-detail summary if the stream is complete
+    pub(crate) fn index_stream(&mut self, stream_id: usize) {
+        let Some(stream) = self.tx_streams.get(&stream_id) else { return };
+        for gen in stream.generators.iter().filter_map(|gen_id| self.tx_generators.get(gen_id)) {
+            let index = IntervalIndex::new(gen.transactions.iter().zip(0..));
+            self.interval_indices.insert(TxSource::Generator(gen.id), index);
+        }
+        let index = IntervalIndex::new(stream.generators
+            .iter()
+            .filter_map(|gen_id| self.tx_generators.get(gen_id))
+            .flat_map(|gen| gen.transactions.iter().zip(0..)));
+        self.interval_indices.insert(TxSource::Stream(stream_id), index);
+
+        if stream.transactions_loaded {
+            let summary = LodSummary::new(self.source_transactions(TxSource::Stream(stream_id)));
+            self.lod_summaries.insert(stream_id, summary);
+        } else {
+            self.lod_summaries.remove(&stream_id);
+        }
+    }
+
+    /// Relations between transactions. If the FTR is backed by a file, the relations of a transaction are loaded
+    /// together with it, see `load_relations()` to load all of them.
+    /// Relations are only ever appended, so their positions, e.g. in `Transaction::inc_relations`, stay valid.
+    pub fn relations(&self) -> &[TxRelation] {
+        &self.tx_relations
+    }
+
+    pub fn get_relation(&self, idx: usize) -> Option<&TxRelation> {
+        self.tx_relations.get(idx)
+    }
+
+    /// Relations whose source or sink stream is not known yet, together with their position in `relations()`.
+    /// The streams get resolved when the transactions of the relation are loaded.
+    pub fn unresolved_relations(&self) -> impl Iterator<Item = (usize, &TxRelation)> {
+        self.tx_relations
+            .iter()
+            .enumerate()
+            .filter(|(_, rel)| rel.source_stream_id.is_none() || rel.sink_stream_id.is_none())
     }
 
     pub fn get_generator(&self, gen_id: usize) -> Option<&TxGenerator> {
         self.tx_generators.get(&gen_id)
     }
 
+    /// Returns the position of the first attribute with the given name in the transactions of a generator,
+    /// as far as they have been loaded.
+    pub fn attribute_slot(&self, gen_id: usize, name: &str) -> Option<usize> {
+        self.attribute_slots.get(&gen_id)?.get(name).copied()
+    }
+
+    /// Same as `Transaction::attribute()`, but uses the attribute positions of the generator of `tx`,
+    /// so that repeated lookups don't have to search the attributes. `tx` has to be a transaction of this FTR.
+    pub fn attribute<'a>(&self, tx: &'a Transaction, name: &str) -> Option<&'a Attribute> {
+        self.attribute_slot(tx.event.gen_id, name)
+            .and_then(|slot| tx.attributes.get(slot))
+            .filter(|attr| attr.name == name)
+            .or_else(|| tx.attribute(name))
+    }
+
+    /// Same as `Transaction::get_u64()`, but looks the attribute up like `attribute()`.
+    pub fn get_u64(&self, tx: &Transaction, name: &str) -> color_eyre::Result<u64> {
+        self.required_attribute(tx, name)?.as_u64()
+    }
+
+    /// See `get_u64()`.
+    pub fn get_i64(&self, tx: &Transaction, name: &str) -> color_eyre::Result<i64> {
+        self.required_attribute(tx, name)?.as_i64()
+    }
+
+    /// See `get_u64()`.
+    pub fn get_f64(&self, tx: &Transaction, name: &str) -> color_eyre::Result<f64> {
+        self.required_attribute(tx, name)?.as_f64()
+    }
+
+    /// See `get_u64()`.
+    pub fn get_bool(&self, tx: &Transaction, name: &str) -> color_eyre::Result<bool> {
+        self.required_attribute(tx, name)?.as_bool()
+    }
+
+    /// See `get_u64()`.
+    pub fn get_str<'a>(&self, tx: &'a Transaction, name: &str) -> color_eyre::Result<&'a str> {
+        self.required_attribute(tx, name)?.as_str()
+    }
+
+    /// See `get_u64()`.
+    pub fn get_time(&self, tx: &Transaction, name: &str) -> color_eyre::Result<u64> {
+        self.required_attribute(tx, name)?.as_time()
+    }
+
+    fn required_attribute<'a>(&self, tx: &'a Transaction, name: &str) -> color_eyre::Result<&'a Attribute> {
+        self.attribute(tx, name).ok_or_else(|| eyre!("Transaction {} has no attribute '{}'", tx.event.tx_id, name))
+    }
+
+    // remembers the position of attribute names that have not been seen yet in the generator of `tx`
+    pub(crate) fn add_attribute_slots(&mut self, tx: &Transaction) {
+        let slots = self.attribute_slots.entry(tx.event.gen_id).or_default();
+        for (slot, attr) in tx.attributes.iter().enumerate() {
+            if !slots.contains_key(&attr.name) {
+                slots.insert(attr.name.clone(), slot);
+            }
+        }
+    }
+
     /// Returns the `Optional<TxGenerator>` with the name `gen_name` from the stream with id `stream_id`.
     pub fn get_generator_from_name(&self, stream_id: Option<usize>, gen_name: String) -> Option<&TxGenerator> {
         if let Some(stream_id) = stream_id {
-            self.tx_streams
-                .get(&stream_id)
-                .unwrap()
+            let stream = self.tx_streams.get(&stream_id).unwrap();
+            let indexed = self.scopes
+                .generator_id(&format!("{}.{}", stream.name, gen_name))
+                .and_then(|id| self.tx_generators.get(&id))
+                .filter(|gen| gen.stream_id == stream_id && gen.name == gen_name);
+            if indexed.is_some() {
+                return indexed;
+            }
+            stream
                 .generators
                 .iter()
                 .map(|id| self.tx_generators.get(id).unwrap())
@@ -0,0 +1,99 @@
+use color_eyre::eyre::bail;
+use num_bigint::BigUint;
+
+use crate::concurrency::{to_f64, ConcurrencyProfile};
+use crate::types::Transaction;
+
+/// Most windows `FTR::utilization()` creates, a longer window has to be chosen for longer traces.
+pub const MAX_WINDOWS: usize = 1 << 20;
+
+/// Options for `FTR::utilization()`.
+#[derive(Debug, Clone)]
+pub struct UtilizationOptions {
+    window: BigUint,
+    sum_of: Option<String>,
+}
+
+impl UtilizationOptions {
+    /// Windows of the given length, in the time scale of the trace.
+    pub fn new(window: impl Into<BigUint>) -> Self {
+        Self { window: window.into().max(BigUint::from(1u8)), sum_of: None }
+    }
+
+    /// Adds up the numeric values of the attribute with this name, e.g. `data_size`, to get the bandwidth.
+    pub fn sum_of(mut self, name: &str) -> Self {
+        self.sum_of = Some(name.to_string());
+        self
+    }
+}
+
+/// Activity of a stream or generator from `start` up to, but not including, `end`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct UtilizationWindow {
+    pub start: BigUint,
+    pub end: BigUint,
+    /// Time in which at least one transaction is in flight
+    pub busy: BigUint,
+    /// Number of transactions that start in the window
+    pub count: usize,
+    /// Sum of the attribute values of the transactions that start in the window
+    pub sum: f64,
+}
+
+impl UtilizationWindow {
+    /// Share of the window in which at least one transaction is in flight.
+    pub fn busy_fraction(&self) -> f64 {
+        to_f64(&self.busy) / to_f64(&(&self.end - &self.start))
+    }
+
+    /// Transactions per time unit of the trace.
+    pub fn rate(&self) -> f64 {
+        self.count as f64 / to_f64(&(&self.end - &self.start))
+    }
+
+    /// Sum of the attribute values per time unit of the trace.
+    pub fn bandwidth(&self) -> f64 {
+        self.sum / to_f64(&(&self.end - &self.start))
+    }
+}
+
+// divides `[0, max_timestamp]` into windows and fills them with the transactions
+pub(crate) fn utilization<'a>(transactions: impl IntoIterator<Item = &'a Transaction>, max_timestamp: &BigUint, options: &UtilizationOptions) -> color_eyre::Result<Vec<UtilizationWindow>> {
+    let window = &options.window;
+    let num_windows = (max_timestamp + window - 1u8) / window;
+    let num_windows = match usize::try_from(&num_windows) {
+        Ok(num_windows) if num_windows <= MAX_WINDOWS => num_windows.max(1),
+        _ => bail!("Windows of length {} divide the trace into {} windows, more than the {} supported", window, num_windows, MAX_WINDOWS),
+    };
+    let mut windows: Vec<UtilizationWindow> = (0..num_windows)
+        .map(|idx| UtilizationWindow {
+            start: window * idx,
+            end: window * (idx + 1),
+            busy: BigUint::default(),
+            count: 0,
+            sum: 0.0,
+        })
+        .collect();
+    // transactions at or after the end of the last window are counted in it
+    let index = |time: &BigUint| usize::try_from(time / window).unwrap_or(usize::MAX).min(num_windows - 1);
+
+    let transactions: Vec<&Transaction> = transactions.into_iter().collect();
+    for tx in &transactions {
+        let window = &mut windows[index(&tx.event.start_time)];
+        window.count += 1;
+        if let Some(name) = &options.sum_of {
+            window.sum += tx.attribute(name).and_then(|attr| attr.as_f64().ok()).unwrap_or(0.0);
+        }
+    }
+
+    for (start, end) in ConcurrencyProfile::new(transactions).intervals_above(0) {
+        for window in &mut windows[index(&start)..=index(&(&end - 1u8))] {
+            let from = (&start).max(&window.start);
+            let to = (&end).min(&window.end);
+            if from < to {
+                window.busy += to - from;
+            }
+        }
+    }
+    Ok(windows)
+}